@@ -0,0 +1,63 @@
+//! The quadratic Frobenius test against the Miller-Rabin test on a 2048-bit prime,
+//! for a single round and for the rounds of an error probability of `2^-128`
+//!
+//! A single round of both tests includes the trial division, which is up to `50000`
+//! for the quadratic Frobenius test.
+//!
+//! Run with `cargo bench -p yoshi389111-miller-rabin --bench frobenius`.
+
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use num_bigint::BigUint;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use yoshi389111_miller_rabin::{
+    check_primality_with_rng, is_probable_prime_frobenius_with_rng, is_probable_prime_with_rng,
+    min_rounds_frobenius,
+};
+
+/// Bit length of the prime
+const BITS: u64 = 2048;
+
+/// Miller-Rabin rounds for `2^-128` for any candidate
+const MILLER_RABIN_ROUNDS: usize = 64;
+
+/// The smallest prime above `2^2047 + 2^2046`
+fn prime() -> BigUint {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut w = (BigUint::from(3u8) << (BITS - 2)) + 1u8;
+    while !is_probable_prime_with_rng(&w, 1, &mut rng) {
+        w += 2u8;
+    }
+    w
+}
+
+fn bench_tests(c: &mut Criterion) {
+    let w = prime();
+    let mut rng = StdRng::seed_from_u64(0);
+    let frobenius_rounds = min_rounds_frobenius(128);
+
+    let mut group = c.benchmark_group("round_2048");
+    group.sample_size(20);
+    group.bench_function("frobenius", |bencher| {
+        bencher.iter(|| is_probable_prime_frobenius_with_rng(black_box(&w), 1, &mut rng))
+    });
+    group.bench_function("miller_rabin", |bencher| {
+        bencher.iter(|| check_primality_with_rng(black_box(&w), 1, &mut rng))
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("error_2^-128_2048");
+    group.sample_size(10);
+    group.bench_function("frobenius", |bencher| {
+        bencher.iter(|| {
+            is_probable_prime_frobenius_with_rng(black_box(&w), frobenius_rounds, &mut rng)
+        })
+    });
+    group.bench_function("miller_rabin", |bencher| {
+        bencher.iter(|| check_primality_with_rng(black_box(&w), MILLER_RABIN_ROUNDS, &mut rng))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_tests);
+criterion_main!(benches);
@@ -0,0 +1,62 @@
+//! Miller-Rabin rounds on a 3072-bit prime: the Montgomery backend of the crate
+//! against the same round written with `BigUint::modpow`
+//!
+//! Run with `cargo bench -p yoshi389111-miller-rabin --bench montgomery`.
+
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use num_bigint::BigUint;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use yoshi389111_miller_rabin::{is_probable_prime_with_rng, miller_rabin_with_bases};
+
+/// Bit length of the prime
+const BITS: u64 = 3072;
+
+/// The smallest prime above `2^3071 + 2^3070`
+fn prime() -> BigUint {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut w = (BigUint::from(3u8) << (BITS - 2)) + 1u8;
+    while !is_probable_prime_with_rng(&w, 1, &mut rng) {
+        w += 2u8;
+    }
+    w
+}
+
+/// One round (steps 4.3 - 4.7) with `BigUint::modpow` and a division for each squaring
+fn modpow_round(w: &BigUint, b: &BigUint) -> bool {
+    let w_minus_1 = w - 1u8;
+    let a = w_minus_1.trailing_zeros().unwrap();
+    let m = &w_minus_1 >> a;
+    let one = BigUint::from(1u8);
+    let mut z = b.modpow(&m, w);
+    if z == one || z == w_minus_1 {
+        return true;
+    }
+    for _ in 1..a {
+        z = &z * &z % w;
+        if z == w_minus_1 {
+            return true;
+        }
+        if z == one {
+            return false;
+        }
+    }
+    false
+}
+
+fn bench_rounds(c: &mut Criterion) {
+    let w = prime();
+    let b = BigUint::from(3u8);
+    let mut group = c.benchmark_group("miller_rabin_round_3072");
+    group.sample_size(20);
+    group.bench_function("montgomery", |bencher| {
+        bencher.iter(|| miller_rabin_with_bases(black_box(&w), std::slice::from_ref(&b)))
+    });
+    group.bench_function("biguint_modpow", |bencher| {
+        bencher.iter(|| modpow_round(black_box(&w), &b))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_rounds);
+criterion_main!(benches);
@@ -0,0 +1,86 @@
+//! Baillie-PSW primality test
+//!
+//! ## References
+//!
+//! - R. Baillie and S. S. Wagstaff, Jr., "Lucas Pseudoprimes", Math. Comp. 35 (1980)
+//! - <https://en.wikipedia.org/wiki/Baillie%E2%80%93PSW_primality_test>
+
+use crate::lucas::{is_strong_lucas_probable_prime, selfridge_params};
+use crate::montgomery::MontgomeryContext;
+use crate::{TWO, miller_rabin_round, trial_division};
+use num_bigint::BigUint;
+
+/// Check if a BigUint is probably prime using the Baillie-PSW test
+///
+/// The test consists of trial division by small primes,
+/// a strong probable prime test to base 2,
+/// and a strong Lucas probable prime test with Selfridge's parameters.
+///
+/// ## Notes
+///
+/// No composite number passing this test is known,
+/// and it is proven that there is none below `2^64`.
+/// The test uses no random numbers, so the result is always reproducible.
+///
+/// ## Params
+///
+/// - `w`: the number to be tested for primality
+///
+/// ## Returns
+///
+/// - `true` if `w` is probably prime
+/// - `false` if `w` is definitely composite
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use yoshi389111_miller_rabin::is_bpsw_prime;
+///
+/// let w = BigUint::from(389_111_u64);
+/// assert!(is_bpsw_prime(&w));
+/// ```
+pub fn is_bpsw_prime(w: &BigUint) -> bool {
+    if let Some(result) = trial_division(w) {
+        return result.is_probable_prime();
+    }
+
+    let w_minus_1 = w - 1u8;
+    let a = w_minus_1.trailing_zeros().expect("always w >= 2");
+    let m = &w_minus_1 >> a;
+    if !miller_rabin_round(&MontgomeryContext::new(w), &m, a, &TWO) {
+        return false;
+    }
+
+    match selfridge_params(w) {
+        Some(params) => is_strong_lucas_probable_prime(w, &params),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_bpsw_prime_with_primes() {
+        assert!(is_bpsw_prime(&BigUint::from(2u8)));
+        assert!(is_bpsw_prime(&BigUint::from(
+            18_446_744_073_709_551_557_u64
+        )));
+        let m521 = (BigUint::from(1u8) << 521) - 1u8;
+        assert!(is_bpsw_prime(&m521));
+    }
+
+    #[test]
+    fn is_bpsw_prime_with_composites() {
+        assert!(!is_bpsw_prime(&BigUint::from(1u8)));
+        // strong pseudoprime to base 2
+        assert!(!is_bpsw_prime(&BigUint::from(3_215_031_751_u64)));
+        // perfect square without small factors
+        let p = BigUint::from(18_446_744_073_709_551_557_u64);
+        assert!(!is_bpsw_prime(&(&p * &p)));
+        let m523 = (BigUint::from(1u8) << 523) - 1u8;
+        assert!(!is_bpsw_prime(&m523));
+    }
+}
@@ -0,0 +1,288 @@
+//! Constant-time Miller-Rabin probabilistic primality test
+//!
+//! This variant is intended for testing secret candidates, such as RSA prime factors.
+//! An accepted prime always takes the same sequence of operations for a given bit length:
+//!
+//! - trial division computes the remainders by all small primes without early exit
+//! - each round draws the same amount of random bits, and runs a square-and-multiply ladder
+//!   over all bits of `w - 1`, so the exponent of two in `w - 1` is not revealed
+//! - comparisons and selections are done with masks instead of branches
+//!
+//! A composite candidate may be rejected early, which leaks only that it was rejected.
+//! The bit length of the candidate is treated as public.
+//!
+//! ## References
+//!
+//! - <https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.186-5.pdf>
+//!   - Appendix B.3.1 Miller-Rabin Probabilistic Primality Test
+
+use crate::Lazy;
+use crate::montgomery::MontgomeryContext;
+use crate::{PRIMES, is_prime_u64};
+use alloc::vec;
+use alloc::vec::Vec;
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq, ConstantTimeGreater};
+
+/// Odd small primes for trial division, with `floor((2^64 - 1) / p)`
+static RECIPROCALS: Lazy<Vec<(u64, u64)>> = Lazy::new(|| {
+    PRIMES
+        .iter()
+        .skip(1)
+        .map(|p| {
+            let p = p.to_u64().expect("small prime");
+            (p, u64::MAX / p)
+        })
+        .collect()
+});
+
+/// Check if a BigUint is probably prime using trial division and the Miller-Rabin test in constant time
+///
+/// The running time for an accepted prime depends only on the bit length of `w` and `iter`,
+/// so the test does not leak the value of a secret prime through timing.
+/// It is several times slower than [`crate::is_probable_prime_with_rng`].
+///
+/// ## Notes
+///
+/// Numbers of 64 bits or less are tested by [`crate::is_prime_u64`], which is not constant time.
+///
+/// ## Params
+///
+/// - `w`: the number to be tested for primality
+/// - `iter`: number of iterations
+/// - `rng`: random number generator
+///
+/// ## Returns
+///
+/// - `true` if `w` is probably prime
+/// - `false` if `w` is definitely composite
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use rand::rngs::OsRng;
+/// use yoshi389111_miller_rabin::is_probable_prime_constant_time_with_rng;
+///
+/// let w = (BigUint::from(1u8) << 127) - 1u8;
+/// let mut rng = OsRng;
+/// assert!(is_probable_prime_constant_time_with_rng(&w, 40, &mut rng));
+/// ```
+pub fn is_probable_prime_constant_time_with_rng<R: rand::Rng + ?Sized>(
+    w: &BigUint,
+    iter: usize,
+    rng: &mut R,
+) -> bool {
+    if let Some(w) = w.to_u64() {
+        return is_prime_u64(w);
+    }
+    if !w.bit(0) || bool::from(has_small_factor(w)) {
+        return false;
+    }
+
+    let ctx = MontgomeryContext::new_constant_time(w);
+    let k = w.iter_u64_digits().len();
+    let bits = w.bits();
+    // `w - 1` is `w` without the lowest bit, since `w` is odd
+    let mut w_minus_1: Vec<u64> = w.iter_u64_digits().collect();
+    w_minus_1[0] &= !1;
+
+    // step 1.
+    let a = trailing_zeros(&w_minus_1, bits);
+    // step 4.
+    for _ in 0..iter {
+        // step 4.1 - 4.2
+        let b = random_base(&ctx, k, rng);
+        // step 4.3 - 4.7
+        if !bool::from(miller_rabin_round(&ctx, &w_minus_1, bits, a, &b)) {
+            return false;
+        }
+    }
+    true // step 5.
+}
+
+/// Check if a BigUint is probably prime using the Miller-Rabin test in constant time with OS random number generator
+///
+/// ## Params
+///
+/// - `w`: the number to be tested for primality
+/// - `iter`: number of iterations
+///
+/// ## Returns
+///
+/// - `true` if `w` is probably prime
+/// - `false` if `w` is definitely composite
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use yoshi389111_miller_rabin::is_probable_prime_constant_time;
+///
+/// let w = (BigUint::from(1u8) << 127) - 1u8;
+/// assert!(is_probable_prime_constant_time(&w, 40));
+/// ```
+#[cfg(feature = "std")]
+pub fn is_probable_prime_constant_time(w: &BigUint, iter: usize) -> bool {
+    is_probable_prime_constant_time_with_rng(w, iter, &mut rand::rngs::OsRng)
+}
+
+/// Check if an odd `w` is divisible by an odd small prime, visiting all primes
+///
+/// The remainder is computed over 32-bit chunks with a precomputed reciprocal,
+/// so no hardware division is used.
+fn has_small_factor(w: &BigUint) -> Choice {
+    let chunks: Vec<u32> = w.iter_u32_digits().collect();
+    let mut divisible = Choice::from(0);
+    for &(p, reciprocal) in RECIPROCALS.iter() {
+        let mut r = 0u64;
+        for &chunk in chunks.iter().rev() {
+            // x < p * 2^32, and the estimated quotient is at most one too small
+            let x = (r << 32) | u64::from(chunk);
+            let q = ((u128::from(x) * u128::from(reciprocal)) >> 64) as u64;
+            r = x - q * p;
+            let (reduced, borrow) = r.overflowing_sub(p);
+            r.conditional_assign(&reduced, Choice::from(u8::from(!borrow)));
+        }
+        divisible |= r.ct_eq(&0);
+    }
+    divisible
+}
+
+/// Count the trailing zeros of the lowest `bits` bits of `x`, visiting all of them
+fn trailing_zeros(x: &[u64], bits: u64) -> u64 {
+    let mut seen = Choice::from(0);
+    let mut count = 0u64;
+    for j in 0..bits {
+        seen |= bit(x, j);
+        count += u64::from((!seen).unwrap_u8());
+    }
+    count
+}
+
+/// Get the `j`-th bit of `x`
+fn bit(x: &[u64], j: u64) -> Choice {
+    Choice::from(((x[(j / 64) as usize] >> (j % 64)) & 1) as u8)
+}
+
+/// Draw a random base in Montgomery form
+///
+/// A random number of `2k` limbs is reduced modulo `w` with Montgomery multiplications,
+/// which avoids a rejection loop depending on `w`. The bias is below `2^-64`.
+fn random_base<R: rand::Rng + ?Sized>(ctx: &MontgomeryContext, k: usize, rng: &mut R) -> Vec<u64> {
+    let low: Vec<u64> = (0..k).map(|_| rng.next_u64()).collect();
+    let high: Vec<u64> = (0..k).map(|_| rng.next_u64()).collect();
+    // (high * R + low) * R mod w
+    let low = ctx.mul(&low, ctx.r2());
+    let high = ctx.mul(&ctx.mul(&high, ctx.r2()), ctx.r2());
+    ctx.add(&low, &high)
+}
+
+/// Run one round of the Miller-Rabin test (steps 4.3 - 4.7) with base `b` in constant time
+///
+/// The ladder visits all bits of `w - 1` from the top. After bit `j`, `x = b^((w - 1) >> j)`,
+/// which is `b^m` for `j = a`, and its squarings below.
+///
+/// ## Params
+///
+/// - `ctx`: Montgomery context for the odd number `w` to be tested for primality
+/// - `w_minus_1`: limbs of `w - 1`
+/// - `bits`: bit length of `w`
+/// - `a`: exponent of two in `w - 1`
+/// - `b`: the base in Montgomery form
+///
+/// ## Returns
+///
+/// - `Choice(1)` if `w` is a strong probable prime to base `b`
+/// - `Choice(0)` if `b` is a witness that `w` is composite
+fn miller_rabin_round(
+    ctx: &MontgomeryContext,
+    w_minus_1: &[u64],
+    bits: u64,
+    a: u64,
+    b: &[u64],
+) -> Choice {
+    // a zero base (with a negligible probability) is not a witness
+    let zero = vec![0u64; b.len()];
+    let mut passed = b.ct_eq(&zero);
+    let mut x = ctx.one().to_vec();
+    let mut y = vec![0u64; b.len()];
+    let mut t = ctx.scratch();
+    for j in (0..bits).rev() {
+        ctx.sqr_assign(&mut x, &mut t);
+        ctx.mul_into(&mut y, &x, b, &mut t);
+        let bit_j = bit(w_minus_1, j);
+        for (x_i, y_i) in x.iter_mut().zip(y.iter()) {
+            x_i.conditional_assign(y_i, bit_j);
+        }
+
+        let in_range = !j.ct_gt(&a);
+        // step 4.4: b^m = 1
+        passed |= j.ct_eq(&a) & x.as_slice().ct_eq(ctx.one());
+        // step 4.4 and 4.5: b^(m * 2^i) = w - 1 for 0 <= i < a
+        passed |= in_range & Choice::from(u8::from(j >= 1)) & x.as_slice().ct_eq(ctx.minus_one());
+    }
+    passed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::seeded_rng;
+    use num_bigint::RandBigInt;
+
+    #[test]
+    fn constant_time_test_matches_miller_rabin() {
+        let mut rng = seeded_rng();
+        for bits in [65, 128, 200, 512] {
+            for _ in 0..200 {
+                let w =
+                    rng.gen_biguint(bits) | BigUint::from(1u8) | (BigUint::from(1u8) << (bits - 1));
+                assert_eq!(
+                    is_probable_prime_constant_time_with_rng(&w, 10, &mut rng),
+                    crate::is_probable_prime_with_rng(&w, 10, &mut rng),
+                    "w = {w}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn constant_time_test_with_special_numbers() {
+        let mut rng = seeded_rng();
+        // 3 * 2^189 + 1: `w - 1` is divisible by a large power of two
+        let prime = (BigUint::from(3u8) << 189) + 1u8;
+        assert!(is_probable_prime_constant_time_with_rng(
+            &prime, 40, &mut rng
+        ));
+        let composite = BigUint::from(18_446_744_073_709_551_557_u64) * 4_294_967_291_u64;
+        assert!(!is_probable_prime_constant_time_with_rng(
+            &composite, 40, &mut rng
+        ));
+        assert!(!is_probable_prime_constant_time_with_rng(
+            &BigUint::from(1u8),
+            40,
+            &mut rng
+        ));
+        assert!(is_probable_prime_constant_time_with_rng(
+            &BigUint::from(389_111_u64),
+            40,
+            &mut rng
+        ));
+    }
+
+    #[test]
+    fn trailing_zeros_visits_all_bits() {
+        assert_eq!(trailing_zeros(&[0, 1 << 3], 128), 67);
+        assert_eq!(trailing_zeros(&[6], 64), 1);
+    }
+
+    #[test]
+    fn has_small_factor_finds_odd_factors() {
+        let w = (BigUint::from(1u8) << 100) + 1u8; // divisible by 17
+        assert!(bool::from(has_small_factor(&w)));
+        let w = BigUint::from(10_007u32 * 10_009);
+        assert!(!bool::from(has_small_factor(&w)));
+    }
+}
@@ -0,0 +1,220 @@
+//! Enhanced Miller-Rabin probabilistic primality test
+//!
+//! ## Reference
+//!
+//! - <https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.186-5.pdf>
+//!   - Appendix B.3.2 Enhanced Miller-Rabin Probabilistic Primality Test
+
+use crate::montgomery::MontgomeryContext;
+use crate::{ONE, Primality, TWO, trial_division};
+use alloc::vec;
+use num_bigint::{BigUint, RandBigInt};
+use num_integer::Integer;
+
+/// Result of the enhanced Miller-Rabin test
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnhancedPrimality {
+    /// The number is zero or one, which is neither prime nor composite
+    NotPrime,
+    /// PROVABLY COMPOSITE WITH FACTOR: the number is composite, with a nontrivial factor
+    ProvablyCompositeWithFactor(BigUint),
+    /// PROVABLY COMPOSITE AND NOT A POWER OF A PRIME
+    ProvablyCompositeNotPowerOfPrime,
+    /// PROBABLY PRIME
+    ProbablyPrime,
+}
+
+/// Check if a BigUint is probably prime using trial division and the enhanced Miller-Rabin test
+///
+/// Unlike [`crate::is_probable_prime_with_rng`], a composite result may carry a nontrivial factor
+/// derived from a gcd, or tell that the number is not a power of a prime.
+///
+/// ## Params
+///
+/// - `w`: the number to be tested for primality
+/// - `iter`: number of iterations
+/// - `rng`: random number generator
+///
+/// ## Returns
+///
+/// - `EnhancedPrimality::ProbablyPrime` if `w` is probably prime
+/// - `EnhancedPrimality::ProvablyCompositeWithFactor` if a nontrivial factor of `w` is found
+/// - `EnhancedPrimality::ProvablyCompositeNotPowerOfPrime` if `w` is composite and not a power of a prime
+/// - `EnhancedPrimality::NotPrime` if `w` is zero or one
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use rand::rngs::OsRng;
+/// use yoshi389111_miller_rabin::{EnhancedPrimality, enhanced_miller_rabin_with_rng};
+///
+/// let w = BigUint::from(389_111_u64);
+/// let mut rng = OsRng;
+/// let result = enhanced_miller_rabin_with_rng(&w, 40, &mut rng);
+/// assert_eq!(result, EnhancedPrimality::ProbablyPrime);
+/// ```
+pub fn enhanced_miller_rabin_with_rng<R: rand::Rng + ?Sized>(
+    w: &BigUint,
+    iter: usize,
+    rng: &mut R,
+) -> EnhancedPrimality {
+    match trial_division(w) {
+        Some(Primality::CompositeWithFactor(p)) => {
+            return EnhancedPrimality::ProvablyCompositeWithFactor(p);
+        }
+        Some(Primality::NotPrime) => return EnhancedPrimality::NotPrime,
+        Some(_) => return EnhancedPrimality::ProbablyPrime,
+        None => {}
+    }
+
+    let w_minus_1 = w - 1u8;
+
+    // step 1.
+    let a = w_minus_1.trailing_zeros().expect("always w >= 2");
+    // step 2.
+    let m = &w_minus_1 >> a;
+    let ctx = MontgomeryContext::new(w);
+    let mut t = ctx.scratch();
+    // step 4.
+    'rounds: for _ in 0..iter {
+        // step 4.1 - 4.2
+        let b = rng.gen_biguint_range(&TWO, &w_minus_1);
+        // step 4.3
+        let g = b.gcd(w);
+        // step 4.4
+        if g > *ONE {
+            return EnhancedPrimality::ProvablyCompositeWithFactor(g);
+        }
+        // step 4.5
+        let mut z = ctx.pow(&ctx.to_mont(&b), &m);
+        // step 4.6
+        if z == ctx.one() || z == ctx.minus_one() {
+            continue;
+        }
+        // step 4.7
+        let mut x = vec![0u64; z.len()];
+        for _ in 1..a {
+            core::mem::swap(&mut x, &mut z); // step 4.7.1
+            ctx.sqr_into(&mut z, &x, &mut t); // step 4.7.2
+            if z == ctx.minus_one() {
+                continue 'rounds; // step 4.7.3
+            }
+            if z == ctx.one() {
+                return composite_result(&ctx.to_biguint(&x), w); // step 4.7.4
+            }
+        }
+        // step 4.8
+        core::mem::swap(&mut x, &mut z);
+        // step 4.9
+        ctx.sqr_into(&mut z, &x, &mut t);
+        // step 4.10 - 4.11
+        if z != ctx.one() {
+            x = z;
+        }
+        return composite_result(&ctx.to_biguint(&x), w);
+    }
+    EnhancedPrimality::ProbablyPrime // step 5.
+}
+
+/// Check if a BigUint is probably prime using the enhanced Miller-Rabin test with OS random number generator
+///
+/// ## Params
+///
+/// - `w`: the number to be tested for primality
+/// - `iter`: number of iterations
+///
+/// ## Returns
+///
+/// - the result of the test (see [`enhanced_miller_rabin_with_rng`])
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use yoshi389111_miller_rabin::{EnhancedPrimality, enhanced_miller_rabin};
+///
+/// let w = BigUint::from(389_111_u64 * 389_111_u64);
+/// let result = enhanced_miller_rabin(&w, 40);
+/// assert_ne!(result, EnhancedPrimality::ProbablyPrime);
+/// ```
+#[cfg(feature = "std")]
+pub fn enhanced_miller_rabin(w: &BigUint, iter: usize) -> EnhancedPrimality {
+    enhanced_miller_rabin_with_rng(w, iter, &mut rand::rngs::OsRng)
+}
+
+/// Steps 4.12 - 4.14: derive a factor of `w` from `x`
+fn composite_result(x: &BigUint, w: &BigUint) -> EnhancedPrimality {
+    // step 4.12
+    let g = (x - 1u8).gcd(w);
+    // step 4.13
+    if g > *ONE {
+        EnhancedPrimality::ProvablyCompositeWithFactor(g)
+    } else {
+        // step 4.14
+        EnhancedPrimality::ProvablyCompositeNotPowerOfPrime
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::seeded_rng;
+
+    #[test]
+    fn enhanced_miller_rabin_with_prime() {
+        let prime = (BigUint::from(3u8) << 189) + 1u8;
+        assert_eq!(
+            enhanced_miller_rabin_with_rng(&prime, 40, &mut seeded_rng()),
+            EnhancedPrimality::ProbablyPrime
+        );
+    }
+
+    #[test]
+    fn enhanced_miller_rabin_finds_factor_of_carmichael_number() {
+        // 12241 * 24481 * 36721: every base coprime to `w` passes the Fermat test,
+        // so a nontrivial square root of one reveals a factor
+        let w = BigUint::from(11_004_252_611_041_u64);
+        match enhanced_miller_rabin_with_rng(&w, 10, &mut seeded_rng()) {
+            EnhancedPrimality::ProvablyCompositeWithFactor(g) => {
+                assert!(g > *ONE && g < w && (&w % &g) == BigUint::from(0u8));
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn enhanced_miller_rabin_with_product_of_two_primes() {
+        let p = BigUint::from(18_446_744_073_709_551_557_u64);
+        let q = BigUint::from(4_294_967_291_u64);
+        let w = &p * &q;
+        match enhanced_miller_rabin_with_rng(&w, 10, &mut seeded_rng()) {
+            EnhancedPrimality::ProvablyCompositeWithFactor(g) => assert!(g == p || g == q),
+            EnhancedPrimality::ProvablyCompositeNotPowerOfPrime => {}
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn enhanced_miller_rabin_with_prime_power() {
+        // a power of a prime is never reported as "not a power of a prime"
+        let p = BigUint::from(10_007_u32);
+        let w = p.pow(5);
+        assert!(matches!(
+            enhanced_miller_rabin_with_rng(&w, 10, &mut seeded_rng()),
+            EnhancedPrimality::ProvablyCompositeWithFactor(_)
+        ));
+    }
+
+    #[test]
+    fn enhanced_miller_rabin_with_small_numbers() {
+        assert_eq!(
+            enhanced_miller_rabin_with_rng(&BigUint::from(1u8), 10, &mut seeded_rng()),
+            EnhancedPrimality::NotPrime
+        );
+        assert_eq!(
+            enhanced_miller_rabin_with_rng(&BigUint::from(21u8), 10, &mut seeded_rng()),
+            EnhancedPrimality::ProvablyCompositeWithFactor(BigUint::from(3u8))
+        );
+    }
+}
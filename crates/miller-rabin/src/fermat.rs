@@ -0,0 +1,204 @@
+//! Fermat, Euler-Jacobi and Solovay-Strassen tests
+//!
+//! The tests to a single base follow the definitions of the pseudoprimes,
+//! so they do not use trial division: `341` is a Fermat pseudoprime to base two.
+//! The Solovay-Strassen test is a complete primality test like [`crate::is_probable_prime`].
+//!
+//! ## References
+//!
+//! - R. Solovay and V. Strassen, "A fast Monte-Carlo test for primality", SIAM J. Comput. 6 (1977)
+//! - R. Crandall and C. Pomerance, "Prime Numbers: A Computational Perspective", Section 3.5
+
+use crate::jacobi::jacobi;
+use crate::{ONE, TWO, trial_division};
+use num_bigint::{BigUint, RandBigInt};
+use num_integer::Integer;
+
+/// Check if a BigUint is a Fermat probable prime to the base `b`
+///
+/// `w` is a Fermat probable prime to base `b` if `b^(w - 1) ≡ 1 (mod w)`.
+///
+/// ## Params
+///
+/// - `w`: the number to be tested
+/// - `b`: the base
+///
+/// ## Returns
+///
+/// - `true` if `w` is a Fermat probable prime to base `b`
+/// - `false` otherwise, including `w` less than two
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use yoshi389111_miller_rabin::is_fermat_prp;
+///
+/// // 341 = 11 * 31 is a Fermat pseudoprime to base 2, but not to base 3
+/// let w = BigUint::from(341u16);
+/// assert!(is_fermat_prp(&w, &BigUint::from(2u8)));
+/// assert!(!is_fermat_prp(&w, &BigUint::from(3u8)));
+/// ```
+pub fn is_fermat_prp(w: &BigUint, b: &BigUint) -> bool {
+    if w < &TWO {
+        return false;
+    }
+    b.modpow(&(w - 1u8), w) == *ONE
+}
+
+/// Check if a BigUint is an Euler-Jacobi probable prime to the base `b`
+///
+/// An odd `w` is an Euler-Jacobi probable prime to base `b`
+/// if `b^((w - 1) / 2) ≡ (b/w) (mod w)` with the Jacobi symbol `(b/w) != 0`.
+///
+/// ## Params
+///
+/// - `w`: the number to be tested
+/// - `b`: the base
+///
+/// ## Returns
+///
+/// - `true` if `w` is an Euler-Jacobi probable prime to base `b`, or `w` is two
+/// - `false` otherwise, including even `w` other than two
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use yoshi389111_miller_rabin::is_euler_jacobi_prp;
+///
+/// // 561 = 3 * 11 * 17 is an Euler-Jacobi pseudoprime to base 2, 341 is not
+/// assert!(is_euler_jacobi_prp(&BigUint::from(561u16), &BigUint::from(2u8)));
+/// assert!(!is_euler_jacobi_prp(&BigUint::from(341u16), &BigUint::from(2u8)));
+/// ```
+pub fn is_euler_jacobi_prp(w: &BigUint, b: &BigUint) -> bool {
+    if w.is_even() {
+        return w == &*TWO;
+    }
+    if w == &*ONE {
+        return false;
+    }
+    let expected = match jacobi(b, w) {
+        1 => ONE.clone(),
+        -1 => w - 1u8,
+        _ => return false,
+    };
+    b.modpow(&(w >> 1), w) == expected
+}
+
+/// Check if a BigUint is probably prime using trial division and the Solovay-Strassen test
+///
+/// Each round is an Euler-Jacobi test to a random base,
+/// and a composite number passes it with a probability of at most `1/2`.
+///
+/// ## Params
+///
+/// - `w`: the number to be tested for primality
+/// - `iter`: number of iterations
+/// - `rng`: random number generator
+///
+/// ## Returns
+///
+/// - `true` if `w` is probably prime
+/// - `false` if `w` is definitely composite
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use rand::rngs::OsRng;
+/// use yoshi389111_miller_rabin::is_probable_prime_solovay_strassen_with_rng;
+///
+/// let w = BigUint::from(389_111_u64);
+/// let mut rng = OsRng;
+/// assert!(is_probable_prime_solovay_strassen_with_rng(&w, 40, &mut rng));
+/// ```
+pub fn is_probable_prime_solovay_strassen_with_rng<R: rand::Rng + ?Sized>(
+    w: &BigUint,
+    iter: usize,
+    rng: &mut R,
+) -> bool {
+    if let Some(result) = trial_division(w) {
+        return result.is_probable_prime();
+    }
+    let w_minus_1 = w - 1u8;
+    (0..iter).all(|_| {
+        let b = rng.gen_biguint_range(&TWO, &w_minus_1);
+        is_euler_jacobi_prp(w, &b)
+    })
+}
+
+/// Check if a BigUint is probably prime using the Solovay-Strassen test with OS random number generator
+///
+/// ## Params
+///
+/// - `w`: the number to be tested for primality
+/// - `iter`: number of iterations
+///
+/// ## Returns
+///
+/// - `true` if `w` is probably prime
+/// - `false` if `w` is definitely composite
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use yoshi389111_miller_rabin::is_probable_prime_solovay_strassen;
+///
+/// let w = BigUint::from(389_111_u64);
+/// assert!(is_probable_prime_solovay_strassen(&w, 40));
+/// ```
+#[cfg(feature = "std")]
+pub fn is_probable_prime_solovay_strassen(w: &BigUint, iter: usize) -> bool {
+    is_probable_prime_solovay_strassen_with_rng(w, iter, &mut rand::rngs::OsRng)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::seeded_rng;
+
+    #[test]
+    fn fermat_and_euler_jacobi_pseudoprimes() {
+        let two = BigUint::from(2u8);
+        // Fermat pseudoprimes to base 2
+        for w in [341u32, 561, 645, 1105, 1387, 1729, 1905, 2047] {
+            assert!(is_fermat_prp(&BigUint::from(w), &two), "w = {w}");
+        }
+        // Euler-Jacobi pseudoprimes to base 2
+        let euler_jacobi = [561u32, 1105, 1729, 1905, 2047, 2465, 3277, 4033, 4681];
+        for w in (3u32..5000).step_by(2) {
+            let is_prime = crate::is_prime_u32(w);
+            assert_eq!(
+                is_euler_jacobi_prp(&BigUint::from(w), &two),
+                is_prime || euler_jacobi.contains(&w),
+                "w = {w}"
+            );
+        }
+        assert!(is_euler_jacobi_prp(&two, &BigUint::from(3u8)));
+        assert!(!is_fermat_prp(&BigUint::from(1u8), &two));
+    }
+
+    #[test]
+    fn solovay_strassen_with_primes_and_composites() {
+        let mut rng = seeded_rng();
+        let m127 = (BigUint::from(1u8) << 127) - 1u8;
+        assert!(is_probable_prime_solovay_strassen_with_rng(
+            &m127, 40, &mut rng
+        ));
+        // Carmichael number without small factors: 10831 * 21661 * 32491
+        let carmichael = BigUint::from(7_622_722_964_881_u64);
+        assert!(is_fermat_prp(&carmichael, &BigUint::from(2u8)));
+        assert!(!is_probable_prime_solovay_strassen_with_rng(
+            &carmichael,
+            40,
+            &mut rng
+        ));
+        assert!(!is_probable_prime_solovay_strassen_with_rng(
+            &BigUint::from(0u8),
+            40,
+            &mut rng
+        ));
+    }
+}
@@ -0,0 +1,441 @@
+//! Integer backends for the Miller-Rabin test
+//!
+//! [`PrimalityInteger`] collects the operations the Miller-Rabin test needs,
+//! so that [`crate::is_probable_prime`] accepts other integer types than `BigUint`
+//! without converting the candidate at the boundary.
+//!
+//! - `u32`, `u64` and `u128` use the deterministic tests of this crate
+//! - `BigUint` uses [`crate::check_primality_with_rng`]
+//! - `crypto_bigint::Uint` is supported with the `crypto-bigint` feature
+//!
+//! Other types only implement the required methods,
+//! and get trial division followed by the Miller-Rabin test.
+//!
+//! ## References
+//!
+//! - <https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.186-5.pdf>
+//!   - Appendix B.3.1 Miller-Rabin Probabilistic Primality Test
+
+use crate::Lazy;
+use crate::MAX_SMALL_PRIME;
+use crate::PRIMES;
+use crate::small_int::add_mod_u128;
+use alloc::vec::Vec;
+use num_bigint::{BigUint, RandBigInt};
+use num_traits::ToPrimitive;
+
+/// The small primes of [`PRIMES`] as `u32`, for trial division of any backend
+static SMALL_PRIMES: Lazy<Vec<u32>> = Lazy::new(|| {
+    PRIMES
+        .iter()
+        .map(|p| p.to_u32().expect("small primes fit in u32"))
+        .collect()
+});
+
+/// Unsigned integer type that can be tested for primality
+///
+/// The required methods are the building blocks of the Miller-Rabin test:
+/// comparison, trailing zeros, modular exponentiation and random numbers in a range.
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use yoshi389111_miller_rabin::is_probable_prime;
+///
+/// assert!(is_probable_prime(&389_111_u64, 40));
+/// assert!(is_probable_prime(&((1u128 << 127) - 1), 40));
+/// assert!(is_probable_prime(&BigUint::from(389_111_u64), 40));
+/// ```
+pub trait PrimalityInteger: Clone + Ord {
+    /// Convert a small number
+    fn from_u32(n: u32) -> Self;
+
+    /// Count the trailing zero bits, or `None` if `self` is zero
+    fn trailing_zeros(&self) -> Option<u64>;
+
+    /// Calculate `self >> bits`
+    fn shr(&self, bits: u64) -> Self;
+
+    /// Calculate `self - n`, where `self >= n`
+    fn sub_u32(&self, n: u32) -> Self;
+
+    /// Calculate `self mod d` for a nonzero `d`
+    fn rem_u32(&self, d: u32) -> u32;
+
+    /// Calculate `self * rhs mod m` for `self, rhs < m` and an odd `m > 1`
+    fn mul_mod(&self, rhs: &Self, m: &Self) -> Self;
+
+    /// Calculate `self^exp mod m` for `self < m` and an odd `m > 1`
+    fn modpow(&self, exp: &Self, m: &Self) -> Self;
+
+    /// Generate a random number in `[low, high)`
+    fn random_range<R: rand::Rng + ?Sized>(rng: &mut R, low: &Self, high: &Self) -> Self;
+
+    /// Check if `self` is probably prime
+    ///
+    /// The default implementation uses trial division and the Miller-Rabin test
+    /// built from the required methods.
+    /// Types with a faster or deterministic test override it.
+    ///
+    /// ## Params
+    ///
+    /// - `iter`: number of iterations
+    /// - `rng`: random number generator
+    ///
+    /// ## Returns
+    ///
+    /// - `true` if `self` is probably prime
+    /// - `false` if `self` is definitely composite
+    fn is_probable_prime_with_rng<R: rand::Rng + ?Sized>(&self, iter: usize, rng: &mut R) -> bool {
+        generic_is_probable_prime_with_rng(self, iter, rng)
+    }
+}
+
+/// Check if `w` is probably prime using trial division and the Miller-Rabin test on any backend
+fn generic_is_probable_prime_with_rng<T: PrimalityInteger, R: rand::Rng + ?Sized>(
+    w: &T,
+    iter: usize,
+    rng: &mut R,
+) -> bool {
+    let two = T::from_u32(2);
+    if *w < two {
+        return false;
+    }
+    for &p in SMALL_PRIMES.iter() {
+        if w.rem_u32(p) == 0 {
+            return *w == T::from_u32(p);
+        }
+    }
+    // no prime factor up to `MAX_SMALL_PRIME`, and `MAX_SMALL_PRIME^2` fits in `u32`
+    if *w <= T::from_u32((MAX_SMALL_PRIME * MAX_SMALL_PRIME) as u32) {
+        return true;
+    }
+
+    let one = T::from_u32(1);
+    let w_minus_1 = w.sub_u32(1);
+    // step 1.
+    let a = w_minus_1.trailing_zeros().expect("always w >= 2");
+    // step 2.
+    let m = w_minus_1.shr(a);
+    // step 4.
+    (0..iter).all(|_| {
+        // step 4.1 - 4.2
+        let b = T::random_range(rng, &two, &w_minus_1);
+        // step 4.3 - 4.4
+        let mut z = b.modpow(&m, w);
+        if z == one || z == w_minus_1 {
+            return true;
+        }
+        // step 4.5 - 4.7
+        for _ in 1..a {
+            z = z.mul_mod(&z, w);
+            if z == w_minus_1 {
+                return true;
+            }
+            if z == one {
+                return false;
+            }
+        }
+        false
+    })
+}
+
+impl PrimalityInteger for u32 {
+    fn from_u32(n: u32) -> Self {
+        n
+    }
+
+    fn trailing_zeros(&self) -> Option<u64> {
+        (*self != 0).then(|| u64::from(u32::trailing_zeros(*self)))
+    }
+
+    fn shr(&self, bits: u64) -> Self {
+        self >> bits
+    }
+
+    fn sub_u32(&self, n: u32) -> Self {
+        self - n
+    }
+
+    fn rem_u32(&self, d: u32) -> u32 {
+        self % d
+    }
+
+    fn mul_mod(&self, rhs: &Self, m: &Self) -> Self {
+        (u64::from(*self) * u64::from(*rhs) % u64::from(*m)) as u32
+    }
+
+    fn modpow(&self, exp: &Self, m: &Self) -> Self {
+        u64::from(*self).modpow(&u64::from(*exp), &u64::from(*m)) as u32
+    }
+
+    fn random_range<R: rand::Rng + ?Sized>(rng: &mut R, low: &Self, high: &Self) -> Self {
+        rng.gen_range(*low..*high)
+    }
+
+    fn is_probable_prime_with_rng<R: rand::Rng + ?Sized>(
+        &self,
+        _iter: usize,
+        _rng: &mut R,
+    ) -> bool {
+        crate::is_prime_u32(*self)
+    }
+}
+
+impl PrimalityInteger for u64 {
+    fn from_u32(n: u32) -> Self {
+        u64::from(n)
+    }
+
+    fn trailing_zeros(&self) -> Option<u64> {
+        (*self != 0).then(|| u64::from(u64::trailing_zeros(*self)))
+    }
+
+    fn shr(&self, bits: u64) -> Self {
+        self >> bits
+    }
+
+    fn sub_u32(&self, n: u32) -> Self {
+        self - u64::from(n)
+    }
+
+    fn rem_u32(&self, d: u32) -> u32 {
+        (self % u64::from(d)) as u32
+    }
+
+    fn mul_mod(&self, rhs: &Self, m: &Self) -> Self {
+        (u128::from(*self) * u128::from(*rhs) % u128::from(*m)) as u64
+    }
+
+    fn modpow(&self, exp: &Self, m: &Self) -> Self {
+        let mut base = self % m;
+        let mut exp = *exp;
+        let mut result = 1 % m;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result.mul_mod(&base, m);
+            }
+            base = base.mul_mod(&base, m);
+            exp >>= 1;
+        }
+        result
+    }
+
+    fn random_range<R: rand::Rng + ?Sized>(rng: &mut R, low: &Self, high: &Self) -> Self {
+        rng.gen_range(*low..*high)
+    }
+
+    fn is_probable_prime_with_rng<R: rand::Rng + ?Sized>(
+        &self,
+        _iter: usize,
+        _rng: &mut R,
+    ) -> bool {
+        crate::is_prime_u64(*self)
+    }
+}
+
+impl PrimalityInteger for u128 {
+    fn from_u32(n: u32) -> Self {
+        u128::from(n)
+    }
+
+    fn trailing_zeros(&self) -> Option<u64> {
+        (*self != 0).then(|| u64::from(u128::trailing_zeros(*self)))
+    }
+
+    fn shr(&self, bits: u64) -> Self {
+        self >> bits
+    }
+
+    fn sub_u32(&self, n: u32) -> Self {
+        self - u128::from(n)
+    }
+
+    fn rem_u32(&self, d: u32) -> u32 {
+        (self % u128::from(d)) as u32
+    }
+
+    fn mul_mod(&self, rhs: &Self, m: &Self) -> Self {
+        // double-and-add, since the product does not fit in `u128`
+        let mut result = 0;
+        for i in (0..128 - rhs.leading_zeros()).rev() {
+            result = add_mod_u128(result, result, *m);
+            if (rhs >> i) & 1 == 1 {
+                result = add_mod_u128(result, *self, *m);
+            }
+        }
+        result
+    }
+
+    fn modpow(&self, exp: &Self, m: &Self) -> Self {
+        let mut base = self % m;
+        let mut exp = *exp;
+        let mut result = 1 % m;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result.mul_mod(&base, m);
+            }
+            base = base.mul_mod(&base, m);
+            exp >>= 1;
+        }
+        result
+    }
+
+    fn random_range<R: rand::Rng + ?Sized>(rng: &mut R, low: &Self, high: &Self) -> Self {
+        rng.gen_range(*low..*high)
+    }
+
+    fn is_probable_prime_with_rng<R: rand::Rng + ?Sized>(
+        &self,
+        _iter: usize,
+        _rng: &mut R,
+    ) -> bool {
+        crate::is_prime_u128(*self)
+    }
+}
+
+impl PrimalityInteger for BigUint {
+    fn from_u32(n: u32) -> Self {
+        BigUint::from(n)
+    }
+
+    fn trailing_zeros(&self) -> Option<u64> {
+        BigUint::trailing_zeros(self)
+    }
+
+    fn shr(&self, bits: u64) -> Self {
+        self >> bits
+    }
+
+    fn sub_u32(&self, n: u32) -> Self {
+        self - n
+    }
+
+    fn rem_u32(&self, d: u32) -> u32 {
+        (self % d).to_u32().expect("the remainder is less than d")
+    }
+
+    fn mul_mod(&self, rhs: &Self, m: &Self) -> Self {
+        self * rhs % m
+    }
+
+    fn modpow(&self, exp: &Self, m: &Self) -> Self {
+        BigUint::modpow(self, exp, m)
+    }
+
+    fn random_range<R: rand::Rng + ?Sized>(rng: &mut R, low: &Self, high: &Self) -> Self {
+        rng.gen_biguint_range(low, high)
+    }
+
+    fn is_probable_prime_with_rng<R: rand::Rng + ?Sized>(&self, iter: usize, rng: &mut R) -> bool {
+        crate::check_primality_with_rng(self, iter, rng).is_probable_prime()
+    }
+}
+
+#[cfg(feature = "crypto-bigint")]
+impl<const LIMBS: usize> PrimalityInteger for crypto_bigint::Uint<LIMBS> {
+    fn from_u32(n: u32) -> Self {
+        Self::from_u32(n)
+    }
+
+    fn trailing_zeros(&self) -> Option<u64> {
+        (*self != Self::ZERO).then(|| self.trailing_zeros_vartime() as u64)
+    }
+
+    fn shr(&self, bits: u64) -> Self {
+        self.shr_vartime(bits as usize)
+    }
+
+    fn sub_u32(&self, n: u32) -> Self {
+        self.wrapping_sub(&Self::from_u32(n))
+    }
+
+    fn rem_u32(&self, d: u32) -> u32 {
+        let d = crypto_bigint::NonZero::new(crypto_bigint::Limb::from_u32(d)).unwrap();
+        self.div_rem_limb(d).1.0 as u32
+    }
+
+    fn mul_mod(&self, rhs: &Self, m: &Self) -> Self {
+        Self::const_rem_wide(self.mul_wide(rhs), m).0
+    }
+
+    fn modpow(&self, exp: &Self, m: &Self) -> Self {
+        use crypto_bigint::modular::runtime_mod::{DynResidue, DynResidueParams};
+        let params = DynResidueParams::new(m);
+        DynResidue::new(self, params).pow(exp).retrieve()
+    }
+
+    fn random_range<R: rand::Rng + ?Sized>(rng: &mut R, low: &Self, high: &Self) -> Self {
+        // rejection sampling below `high - low` with the bit length of the range
+        let range = high.wrapping_sub(low);
+        let shift = Self::BITS - range.bits_vartime();
+        loop {
+            let words = core::array::from_fn(|_| rng.r#gen());
+            let candidate = Self::from_words(words).shr_vartime(shift);
+            if candidate < range {
+                return low.wrapping_add(&candidate);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::seeded_rng;
+
+    #[test]
+    fn generic_test_matches_deterministic_tests() {
+        let mut rng = seeded_rng();
+        for w in (0u64..20_000).chain(100_000_000..100_010_000) {
+            assert_eq!(
+                generic_is_probable_prime_with_rng(&w, 20, &mut rng),
+                crate::is_prime_u64(w),
+                "w = {w}"
+            );
+        }
+        // strong pseudoprime to the bases 2 to 37 (Sorenson-Webster)
+        let psi12 = 318_665_857_834_031_151_167_461_u128;
+        assert!(!generic_is_probable_prime_with_rng(&psi12, 20, &mut rng));
+        assert!(!generic_is_probable_prime_with_rng(
+            &BigUint::from(psi12),
+            20,
+            &mut rng
+        ));
+        let m127 = (1u128 << 127) - 1;
+        assert!(generic_is_probable_prime_with_rng(&m127, 20, &mut rng));
+        assert!(generic_is_probable_prime_with_rng(
+            &BigUint::from(m127),
+            20,
+            &mut rng
+        ));
+    }
+
+    #[test]
+    fn mul_mod_u128_does_not_overflow() {
+        let m = u128::MAX - 158; // the largest prime below 2^128
+        let a = m - 1;
+        assert_eq!(a.mul_mod(&a, &m), 1);
+        assert_eq!(3u128.modpow(&(m - 1), &m), 1);
+    }
+
+    #[cfg(feature = "crypto-bigint")]
+    #[test]
+    fn crypto_bigint_backend() {
+        use crypto_bigint::U256;
+        // 2^255 - 19
+        let p =
+            U256::from_be_hex("7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffed");
+        assert!(crate::is_probable_prime(&p, 20));
+        let psi12 = U256::from_u128(318_665_857_834_031_151_167_461);
+        assert!(!crate::is_probable_prime(&psi12, 20));
+        for w in 0u32..2000 {
+            assert_eq!(
+                crate::is_probable_prime(&U256::from_u32(w), 20),
+                crate::is_prime_u32(w),
+                "w = {w}"
+            );
+        }
+    }
+}
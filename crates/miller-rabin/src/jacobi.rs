@@ -0,0 +1,117 @@
+//! Jacobi symbol
+//!
+//! ## References
+//!
+//! - H. Cohen, "A Course in Computational Algebraic Number Theory", Algorithm 1.4.10
+
+use num_bigint::{BigInt, BigUint, Sign};
+use num_integer::Integer;
+use num_traits::Zero;
+
+/// Calculate the Jacobi symbol `(a/n)`
+///
+/// ## Panics
+///
+/// Panics if `n` is even (including zero).
+///
+/// ## Params
+///
+/// - `a`: the numerator
+/// - `n`: the denominator (must be odd and positive)
+///
+/// ## Returns
+///
+/// - `1`, `-1` or `0`
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use yoshi389111_miller_rabin::jacobi;
+///
+/// // 2 is a quadratic residue modulo 7 (3^2 = 2), and 3 is not
+/// assert_eq!(jacobi(&BigUint::from(2u8), &BigUint::from(7u8)), 1);
+/// assert_eq!(jacobi(&BigUint::from(3u8), &BigUint::from(7u8)), -1);
+/// assert_eq!(jacobi(&BigUint::from(7u8), &BigUint::from(21u8)), 0);
+/// ```
+pub fn jacobi(a: &BigUint, n: &BigUint) -> i8 {
+    jacobi_bigint(&BigInt::from(a.clone()), n)
+}
+
+/// Calculate the Jacobi symbol `(a/n)` for a BigInt numerator
+///
+/// ## Panics
+///
+/// Panics if `n` is even (including zero).
+///
+/// ## Params
+///
+/// - `a`: the numerator (may be negative)
+/// - `n`: the denominator (must be odd and positive)
+///
+/// ## Returns
+///
+/// - `1`, `-1` or `0`
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::{BigInt, BigUint};
+/// use yoshi389111_miller_rabin::jacobi_bigint;
+///
+/// // -1 is a quadratic non-residue modulo 7
+/// assert_eq!(jacobi_bigint(&BigInt::from(-1), &BigUint::from(7u8)), -1);
+/// ```
+pub fn jacobi_bigint(a: &BigInt, n: &BigUint) -> i8 {
+    assert!(n.is_odd(), "n must be an odd positive number");
+
+    // reduce `a` into `[0, n)`
+    let mut a = match a.sign() {
+        Sign::Minus => {
+            let r = a.magnitude() % n;
+            if r.is_zero() { r } else { n - r }
+        }
+        _ => a.magnitude() % n,
+    };
+    let mut n = n.clone();
+    let mut result = 1;
+    while !a.is_zero() {
+        let tz = a.trailing_zeros().expect("a is not zero");
+        a >>= tz;
+        let n_mod_8 = low_bits(&n) & 7;
+        if tz % 2 == 1 && (n_mod_8 == 3 || n_mod_8 == 5) {
+            result = -result;
+        }
+        if low_bits(&a) & 3 == 3 && n_mod_8 & 3 == 3 {
+            result = -result;
+        }
+        let r = &n % &a;
+        n = a;
+        a = r;
+    }
+    if n == BigUint::from(1u8) { result } else { 0 }
+}
+
+/// Get the lowest 32 bits of a BigUint
+fn low_bits(n: &BigUint) -> u32 {
+    n.iter_u32_digits().next().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jacobi_matches_known_values() {
+        let n = BigUint::from(45u8);
+        let expected = [
+            0, 1, -1, 0, 1, 0, 0, -1, -1, 0, 0, 1, 0, -1, 1, 0, 1, -1, 0, 1, 0, 0, -1, -1, 0,
+        ];
+        for (a, &e) in expected.iter().enumerate() {
+            assert_eq!(jacobi(&BigUint::from(a), &n), e, "a = {a}");
+        }
+        assert_eq!(jacobi_bigint(&BigInt::from(-1), &BigUint::from(7u8)), -1);
+        assert_eq!(jacobi_bigint(&BigInt::from(-7), &BigUint::from(7u8)), 0);
+        assert_eq!(jacobi_bigint(&BigInt::from(-3), &BigUint::from(13u8)), 1);
+    }
+}
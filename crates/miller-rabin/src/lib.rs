@@ -7,6 +7,25 @@
 //!
 //! - Probabilistic: can quickly identify composite numbers, and declares numbers as "probably prime" with a configurable error probability
 //! - Supports both `BigUint` and `BigInt` types
+//! - Generic over integer backends: `u32`, `u64`, `u128`, `BigUint`, and `crypto_bigint::Uint` with the `crypto-bigint` feature
+//! - Deterministic tests for `u32`, `u64` and `u128` without heap allocation, which are proofs
+//!   except for `u128` numbers from `3_317_044_064_679_887_385_961_981`, tested with Baillie-PSW
+//! - Baillie-PSW test without random numbers
+//! - Lucas, strong Lucas and extra strong Lucas probable prime tests
+//! - Fermat, Euler-Jacobi and Solovay-Strassen tests, and the Jacobi symbol
+//! - Quadratic Frobenius test (Grantham) with an error probability below `1/7710` per round
+//! - Detailed results with the witness or the factor that proves compositeness
+//! - Batch testing of many candidates
+//! - Parallel rounds with early cancellation, and parallel batch testing with the `rayon` feature
+//! - `no_std` with `alloc` when the default `std` feature is disabled; the random number generator is then supplied by the caller
+//! - Reproducible Miller-Rabin test with caller-supplied bases
+//! - Enhanced Miller-Rabin test (FIPS 186-5 Appendix B.3.2)
+//! - Number of rounds chosen from a target error probability (FIPS 186-5 Table B.1)
+//! - Constant-time test for secret candidates such as RSA prime factors
+//! - Miller-Rabin rounds on the system GMP library with the `gmp` feature
+//! - Lucas-Lehmer test for Mersenne numbers
+//! - Proth's theorem and Pépin's test for numbers of the form `k * 2^n + 1`, detected automatically
+//! - Lucas-Lehmer-Riesel test for numbers of the form `k * 2^n - 1`, detected automatically
 //!
 //! ## Usage
 //!
@@ -19,15 +38,90 @@
 //! let is_prime = is_probable_prime(&w, 40);
 //! ```
 //!
+//! ## Compatibility
+//!
+//! [`is_probable_prime`] and [`is_probable_prime_with_rng`] are generic over the integer type `T`.
+//! A call that names the type arguments must name `T` as well:
+//! `is_probable_prime_with_rng::<StdRng>(..)` becomes `is_probable_prime_with_rng::<BigUint, StdRng>(..)`,
+//! and `is_probable_prime_with_rng::<BigUint, _>(..)` also works. Calls without type arguments are unchanged.
+//!
 //!! ## Reference
 //!
 //! - <https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.186-5.pdf>
 //!   - Appendix B.3.1 Miller-Rabin Probabilistic Primality Test
+//!   - Appendix B.3.2 Enhanced Miller-Rabin Probabilistic Primality Test
 //!   - Appendix B.3, Table B.1 Minimum number of rounds of M-R testing
 //!     when generating primes for use in RSA Digital Signatures
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+mod batch;
+mod bpsw;
+mod constant_time;
+mod enhanced;
+mod fermat;
+mod frobenius;
+#[cfg(feature = "gmp")]
+mod gmp;
+mod integer;
+mod jacobi;
+mod lucas;
+mod mersenne;
+mod montgomery;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod primality;
+mod proth;
+mod riesel;
+mod rounds;
+mod small_int;
+mod special_form;
+pub use crate::batch::filter_probable_primes_with_rng;
+pub use crate::bpsw::is_bpsw_prime;
+pub use crate::constant_time::is_probable_prime_constant_time_with_rng;
+pub use crate::enhanced::{EnhancedPrimality, enhanced_miller_rabin_with_rng};
+pub use crate::fermat::{
+    is_euler_jacobi_prp, is_fermat_prp, is_probable_prime_solovay_strassen_with_rng,
+};
+pub use crate::frobenius::{is_probable_prime_frobenius_with_rng, min_rounds_frobenius};
+pub use crate::integer::PrimalityInteger;
+pub use crate::jacobi::{jacobi, jacobi_bigint};
+pub use crate::lucas::{is_extra_strong_lucas_prp, is_lucas_prp, is_strong_lucas_prp};
+pub use crate::mersenne::is_mersenne_prime;
+#[cfg(feature = "rayon")]
+pub use crate::parallel::{
+    check_primality_par_with_rng, filter_probable_primes_par, is_probable_prime_par,
+};
+pub use crate::primality::Primality;
+pub use crate::proth::{is_fermat_prime, is_proth_prime};
+pub use crate::riesel::is_riesel_prime;
+pub use crate::rounds::{
+    is_probable_prime_for_error_with_rng, is_probable_prime_with_lucas_for_error_with_rng,
+    min_rounds, min_rounds_with_lucas,
+};
+pub use crate::small_int::{is_prime_u32, is_prime_u64, is_prime_u128};
+// functions with OS random number generator
+#[cfg(feature = "std")]
+pub use crate::{
+    batch::filter_probable_primes, constant_time::is_probable_prime_constant_time,
+    enhanced::enhanced_miller_rabin, fermat::is_probable_prime_solovay_strassen,
+    frobenius::is_probable_prime_frobenius, rounds::is_probable_prime_for_error,
+    rounds::is_probable_prime_with_lucas_for_error,
+};
+
+use crate::montgomery::MontgomeryContext;
+use crate::proth::{proth_form, proth_test};
+use crate::riesel::{riesel_form, riesel_test};
+use alloc::vec;
+use alloc::vec::Vec;
 use num_bigint::{BigUint, RandBigInt};
+use num_traits::ToPrimitive;
+#[cfg(feature = "std")]
 use once_cell::sync::Lazy;
+#[cfg(not(feature = "std"))]
+use spin::Lazy;
 
 /// Maximum prime number for trial division
 const MAX_SMALL_PRIME: usize = 10_000;
@@ -40,18 +134,38 @@ static PRIMES: Lazy<Vec<BigUint>> = Lazy::new(|| {
         .collect()
 });
 
+/// Small primes for trial division, grouped so that the product of each group fits in `u64`
+///
+/// One remainder of `w` modulo the product replaces a multi-precision division for each prime
+/// of the group, like a gcd with the primorial cut into machine words.
+static PRIME_GROUPS: Lazy<Vec<(u64, Vec<u64>)>> = Lazy::new(|| {
+    let mut groups: Vec<(u64, Vec<u64>)> = Vec::new();
+    for p in yoshi389111_prime_iter::new::<u64>().take_while(|p| *p as usize <= MAX_SMALL_PRIME) {
+        match groups.last_mut() {
+            Some((product, primes)) if product.checked_mul(p).is_some() => {
+                *product *= p;
+                primes.push(p);
+            }
+            _ => groups.push((p, vec![p])),
+        }
+    }
+    groups
+});
+
 /// Threshold for using trial division only
 static TRIAL_DIVISION_ONLY_THRESHOLD: Lazy<BigUint> =
     Lazy::new(|| BigUint::from(MAX_SMALL_PRIME * MAX_SMALL_PRIME));
 
-/// BigUint value for zero
-static ZERO: Lazy<BigUint> = Lazy::new(|| BigUint::from(0u8));
 /// BigUint value for one
 static ONE: Lazy<BigUint> = Lazy::new(|| BigUint::from(1u8));
 /// BigUint value for two
 static TWO: Lazy<BigUint> = Lazy::new(|| BigUint::from(2u8));
 
-/// Check if a BigUint is probably prime using trial division and the Miller-Rabin test
+/// Check if a number is probably prime using trial division and the Miller-Rabin test
+///
+/// Any [`PrimalityInteger`] backend is accepted.
+/// `BigUint` numbers of a special form are proven prime or composite (see [`check_primality_with_rng`]),
+/// and `u32`, `u64` and `u128` numbers are tested deterministically (see [`is_prime_u128`]).
 ///
 /// ## Params
 ///
@@ -75,65 +189,307 @@ static TWO: Lazy<BigUint> = Lazy::new(|| BigUint::from(2u8));
 /// let mut rng = OsRng;
 /// let is_prime = is_probable_prime_with_rng(&w, 40, &mut rng);
 /// ```
-pub fn is_probable_prime_with_rng<R: rand::Rng + ?Sized>(
-    w: &BigUint,
+pub fn is_probable_prime_with_rng<T: PrimalityInteger, R: rand::Rng + ?Sized>(
+    w: &T,
     iter: usize,
     rng: &mut R,
 ) -> bool {
-    if w <= &ONE {
-        return false;
+    w.is_probable_prime_with_rng(iter, rng)
+}
+
+/// Check the primality of a BigUint using trial division and the Miller-Rabin test, with a detailed result
+///
+/// ## Notes
+///
+/// Numbers of the form `k * 2^n + 1` or `k * 2^n - 1` with an odd `k < 2^n`
+/// are tested with Proth's theorem or the Lucas-Lehmer-Riesel test instead of the Miller-Rabin test.
+///
+/// ## Params
+///
+/// - `w`: the number to be tested for primality
+/// - `iter`: number of iterations
+/// - `rng`: random number generator
+///
+/// ## Returns
+///
+/// - `Primality::ProvenPrime` if `w` is proven prime by trial division, or by the test for its special form
+/// - `Primality::ProbablyPrime` if `w` passed all `iter` rounds
+/// - `Primality::CompositeWithFactor` if a factor of `w` is found
+/// - `Primality::Composite` if a Miller-Rabin round or the test for the special form found a witness
+/// - `Primality::NotPrime` if `w` is zero or one
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use rand::rngs::OsRng;
+/// use yoshi389111_miller_rabin::{Primality, check_primality_with_rng};
+///
+/// let w = BigUint::from(389_111_u64 * 9973);
+/// let mut rng = OsRng;
+/// let result = check_primality_with_rng(&w, 40, &mut rng);
+/// assert_eq!(result, Primality::CompositeWithFactor(BigUint::from(9973_u32)));
+/// ```
+pub fn check_primality_with_rng<R: rand::Rng + ?Sized>(
+    w: &BigUint,
+    iter: usize,
+    rng: &mut R,
+) -> Primality {
+    if let Some(result) = trial_division(w) {
+        return result;
     }
+    // a proof for the special forms is as cheap as a single Miller-Rabin round
+    if let Some((k, n)) = proth_form(w) {
+        return proth_test(w, &k, n);
+    }
+    if let Some((k, n)) = riesel_form(w) {
+        return riesel_test(w, &k, n);
+    }
+    miller_rabin_with_rng(w, iter, rng)
+}
 
-    if w <= &TRIAL_DIVISION_ONLY_THRESHOLD {
-        // use only trial division for small numbers
-        for p in PRIMES.iter() {
-            if w == p {
-                return true;
-            }
-            if w % p == *ZERO {
-                return false;
-            }
+/// Run the Miller-Rabin test (steps 1 - 5) without trial division
+///
+/// `w` must be an odd number greater than three.
+fn miller_rabin_with_rng<R: rand::Rng + ?Sized>(
+    w: &BigUint,
+    iter: usize,
+    rng: &mut R,
+) -> Primality {
+    let w_minus_1 = w - 1u8;
+    // step 1 - 3.
+    let round = round_tester(w);
+    // step 4.
+    for _ in 0..iter {
+        // step 4.1 - 4.2
+        let b = rng.gen_biguint_range(&TWO, &w_minus_1);
+        // step 4.3 - 4.7
+        if !round(&b) {
+            return Primality::Composite { witness: b };
         }
-        return true;
     }
+    Primality::ProbablyPrime { rounds: iter } // step 5.
+}
 
-    // trial division by small primes
-    for p in PRIMES.iter() {
-        if w % p == *ZERO {
-            return false;
-        }
+/// Check the primality of a BigUint with OS random number generator, with a detailed result
+///
+/// ## Params
+///
+/// - `w`: the number to be tested for primality
+/// - `iter`: number of iterations
+///
+/// ## Returns
+///
+/// - the detailed result (see [`check_primality_with_rng`])
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use yoshi389111_miller_rabin::{Primality, check_primality};
+///
+/// let w = BigUint::from(389_111_u64);
+/// assert_eq!(check_primality(&w, 40), Primality::ProvenPrime);
+/// ```
+#[cfg(feature = "std")]
+pub fn check_primality(w: &BigUint, iter: usize) -> Primality {
+    check_primality_with_rng(w, iter, &mut rand::rngs::OsRng)
+}
+
+/// Check if a BigUint is probably prime using trial division and the Miller-Rabin test with fixed bases
+///
+/// ## Notes
+///
+/// The result is reproducible, and it is a proof when the bases form a deterministic set for `w`:
+/// for example, the first 13 primes (`2` to `41`) for `w < 3_317_044_064_679_887_385_961_981`
+/// (Sorenson-Webster).
+///
+/// ## Params
+///
+/// - `w`: the number to be tested for primality
+/// - `bases`: the bases of the Miller-Rabin rounds
+///
+/// ## Returns
+///
+/// - `true` if `w` is a strong probable prime to all bases (also when every base is skipped,
+///   see [`check_primality_with_bases`])
+/// - `false` if `w` is definitely composite
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use yoshi389111_miller_rabin::miller_rabin_with_bases;
+///
+/// let bases: Vec<BigUint> = [2u8, 3, 5, 7, 11, 13, 17].into_iter().map(BigUint::from).collect();
+/// assert!(miller_rabin_with_bases(&BigUint::from(389_111_u64), &bases));
+/// ```
+pub fn miller_rabin_with_bases(w: &BigUint, bases: &[BigUint]) -> bool {
+    check_primality_with_bases(w, bases).is_probable_prime()
+}
+
+/// Check the primality of a BigUint using trial division and the Miller-Rabin test with fixed bases, with a detailed result
+///
+/// Each base is reduced modulo `w`, and a base congruent to `0`, `1` or `-1` is skipped,
+/// since it cannot be a witness (step 4.2 requires `1 < b < w - 1`).
+///
+/// ## Params
+///
+/// - `w`: the number to be tested for primality
+/// - `bases`: the bases of the Miller-Rabin rounds
+///
+/// ## Returns
+///
+/// - `Primality::ProvenPrime` if `w` is proven prime by trial division
+/// - `Primality::ProbablyPrime` if `w` is a strong probable prime to all bases,
+///   where `rounds` is the number of bases not skipped (`rounds == 0` means no round was run,
+///   so `w` was not tested at all)
+/// - `Primality::CompositeWithFactor` if trial division found a factor of `w`
+/// - `Primality::Composite` with the first base that is a witness
+/// - `Primality::NotPrime` if `w` is zero or one
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use yoshi389111_miller_rabin::{Primality, check_primality_with_bases};
+///
+/// // strong pseudoprime to the bases 2 to 31 (Jaeschke)
+/// let w = BigUint::from(3_825_123_056_546_413_051_u64);
+/// let bases: Vec<BigUint> = [2u8, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37]
+///     .into_iter()
+///     .map(BigUint::from)
+///     .collect();
+/// let result = check_primality_with_bases(&w, &bases);
+/// assert_eq!(result, Primality::Composite { witness: BigUint::from(37u8) });
+/// ```
+pub fn check_primality_with_bases(w: &BigUint, bases: &[BigUint]) -> Primality {
+    if let Some(result) = trial_division(w) {
+        return result;
     }
 
     let w_minus_1 = w - 1u8;
+    // step 1 - 3.
+    let round = round_tester(w);
+    // step 4.
+    let mut rounds = 0;
+    for base in bases {
+        let b = base % w;
+        if b <= *ONE || b == w_minus_1 {
+            continue;
+        }
+        // step 4.3 - 4.7
+        if !round(&b) {
+            return Primality::Composite {
+                witness: base.clone(),
+            };
+        }
+        rounds += 1;
+    }
+    Primality::ProbablyPrime { rounds } // step 5.
+}
+
+/// Check `w` by trial division with small primes
+///
+/// ## Returns
+///
+/// - `Some(Primality::ProvenPrime)` if `w` is a prime number
+/// - `Some(Primality::CompositeWithFactor)` if a prime factor of `w` is found
+/// - `Some(Primality::NotPrime)` if `w` is zero or one
+/// - `None` if the primality of `w` cannot be decided by trial division
+fn trial_division(w: &BigUint) -> Option<Primality> {
+    if w <= &ONE {
+        return Some(Primality::NotPrime);
+    }
 
+    for (product, primes) in PRIME_GROUPS.iter() {
+        let r = rem_u64(w, *product);
+        if let Some(&p) = primes.iter().find(|&&p| r.is_multiple_of(p)) {
+            return Some(if w.to_u64() == Some(p) {
+                Primality::ProvenPrime
+            } else {
+                Primality::CompositeWithFactor(BigUint::from(p))
+            });
+        }
+    }
+
+    // use only trial division for small numbers
+    (w <= &TRIAL_DIVISION_ONLY_THRESHOLD).then_some(Primality::ProvenPrime)
+}
+
+/// Calculate `w mod m` without allocation
+fn rem_u64(w: &BigUint, m: u64) -> u64 {
+    w.iter_u64_digits().rev().fold(0, |r, d| {
+        (((u128::from(r) << 64) | u128::from(d)) % u128::from(m)) as u64
+    })
+}
+
+/// Prepare the Miller-Rabin rounds (steps 1 - 3) for an odd number `w` greater than three
+///
+/// ## Returns
+///
+/// - a function that runs one round (steps 4.3 - 4.7) with the given base,
+///   and returns `false` if the base is a witness that `w` is composite
+#[cfg(not(feature = "gmp"))]
+fn round_tester(w: &BigUint) -> impl Fn(&BigUint) -> bool {
+    let w_minus_1 = w - 1u8;
     // step 1.
     let a = w_minus_1.trailing_zeros().expect("always w >= 2");
     // step 2.
     let m = &w_minus_1 >> a;
-    // step 4.
-    for _ in 0..iter {
-        // step 4.1 - 4.2
-        let b = rng.gen_biguint_range(&TWO, &w_minus_1);
-        // step 4.3
-        let z = b.modpow(&m, w);
-        // step 4.4
-        if z == *ONE || z == w_minus_1 {
-            continue;
+    let ctx = MontgomeryContext::new(w);
+    move |b| miller_rabin_round(&ctx, &m, a, b)
+}
+
+/// Prepare the Miller-Rabin rounds (steps 1 - 3) for an odd number `w` greater than three, using GMP
+///
+/// ## Returns
+///
+/// - a function that runs one round (steps 4.3 - 4.7) with the given base,
+///   and returns `false` if the base is a witness that `w` is composite
+#[cfg(feature = "gmp")]
+fn round_tester(w: &BigUint) -> impl Fn(&BigUint) -> bool {
+    let ctx = crate::gmp::GmpContext::new(w);
+    move |b| ctx.miller_rabin_round(b)
+}
+
+/// Run one round of the Miller-Rabin test (steps 4.3 - 4.7) with base `b`
+///
+/// ## Params
+///
+/// - `ctx`: Montgomery context for the odd number `w` to be tested for primality
+/// - `m`: odd part of `w - 1`
+/// - `a`: exponent of two in `w - 1` (`w - 1 = 2^a * m`)
+/// - `b`: the base
+///
+/// ## Returns
+///
+/// - `true` if `w` is a strong probable prime to base `b`
+/// - `false` if `b` is a witness that `w` is composite
+fn miller_rabin_round(ctx: &MontgomeryContext, m: &BigUint, a: u64, b: &BigUint) -> bool {
+    // step 4.3
+    let mut z = ctx.pow(&ctx.to_mont(b), m);
+    // step 4.4
+    if z == ctx.one() || z == ctx.minus_one() {
+        return true;
+    }
+    // step 4.5
+    let mut t = ctx.scratch();
+    for _ in 1..a {
+        ctx.sqr_assign(&mut z, &mut t); // step 4.5.1
+        if z == ctx.minus_one() {
+            return true; // step 4.7
         }
-        // step 4.5
-        let result = (1..a)
-            .scan(z, |z, _| Some(z.modpow(&TWO, w))) // step 4.5.1
-            .find(|z| z == &w_minus_1 || *z == *ONE);
-        match result {
-            Some(z) if z == w_minus_1 => continue, // step 4.7
-            Some(_) => return false,               // step 4.6
-            None => return false,                  // step 4.6
-        };
-    }
-    true // step 5.
+        if z == ctx.one() {
+            return false; // step 4.6
+        }
+    }
+    false // step 4.6
 }
 
-/// Check if a BigUint number is probably prime using Miller-Rabin test with OS random number generator
+/// Check if a number is probably prime using Miller-Rabin test with OS random number generator
+///
+/// Any [`PrimalityInteger`] backend is accepted (see [`is_probable_prime_with_rng`]).
 ///
 /// ## Params
 ///
@@ -153,8 +509,10 @@ pub fn is_probable_prime_with_rng<R: rand::Rng + ?Sized>(
 ///
 /// let w = BigUint::from(389_111_u64);
 /// let is_prime = is_probable_prime(&w, 40);
+/// assert!(is_probable_prime(&389_111_u64, 40));
 /// ```
-pub fn is_probable_prime(w: &BigUint, iter: usize) -> bool {
+#[cfg(feature = "std")]
+pub fn is_probable_prime<T: PrimalityInteger>(w: &T, iter: usize) -> bool {
     is_probable_prime_with_rng(w, iter, &mut rand::rngs::OsRng)
 }
 
@@ -183,6 +541,7 @@ pub fn is_probable_prime(w: &BigUint, iter: usize) -> bool {
 /// let w = BigInt::from(389_111_i64);
 /// let is_prime = is_probable_prime_bigint(&w, 40);
 /// ```
+#[cfg(feature = "std")]
 pub fn is_probable_prime_bigint(w: &num_bigint::BigInt, iter: usize) -> bool {
     match w.to_biguint() {
         Some(u) => is_probable_prime(&u, iter),
@@ -194,18 +553,117 @@ pub fn is_probable_prime_bigint(w: &num_bigint::BigInt, iter: usize) -> bool {
 mod tests {
     use super::*;
 
+    /// Seeded random number generator for the tests, available without `std`
+    pub(crate) fn seeded_rng() -> rand::rngs::StdRng {
+        rand::SeedableRng::seed_from_u64(389_111)
+    }
+
+    #[cfg(feature = "std")]
     #[test]
     fn test_is_probable_prime_with_prime() {
         let prime = BigUint::from(18446744073709551557_u64);
         assert!(is_probable_prime(&prime, 40));
     }
 
+    #[cfg(feature = "std")]
     #[test]
     fn test_is_probable_prime_with_composite() {
         let composite = BigUint::from(389111_u64 * 389111_u64);
         assert!(!is_probable_prime(&composite, 40));
     }
 
+    #[test]
+    fn test_is_probable_prime_with_prime_congruent_to_1_mod_8() {
+        // `w - 1` is divisible by a large power of two
+        let mut rng = seeded_rng();
+        let prime = BigUint::from(12_294_508_673_u64);
+        assert!(is_probable_prime_with_rng(&prime, 40, &mut rng));
+        let prime = (BigUint::from(3u8) << 189) + 1u8;
+        assert!(is_probable_prime_with_rng(&prime, 40, &mut rng));
+    }
+
+    #[test]
+    fn test_check_primality_reports_details() {
+        let mut rng = seeded_rng();
+        let mut check = |w: u64| check_primality_with_rng(&BigUint::from(w), 10, &mut rng);
+        assert_eq!(check(1), Primality::NotPrime);
+        assert_eq!(check(9973), Primality::ProvenPrime);
+        assert_eq!(
+            check(9973 * 10_007),
+            Primality::CompositeWithFactor(BigUint::from(9973u32))
+        );
+        assert_eq!(
+            check(18_446_744_073_709_551_557),
+            Primality::ProbablyPrime { rounds: 10 }
+        );
+        assert!(matches!(
+            check(10_007 * 10_009),
+            Primality::Composite { .. }
+        ));
+    }
+
+    #[test]
+    fn test_miller_rabin_with_bases() {
+        let primes: Vec<BigUint> = yoshi389111_prime_iter::new::<u8>()
+            .take(13)
+            .map(BigUint::from)
+            .collect();
+        // strong pseudoprime to the first 12 primes (Sorenson-Webster)
+        let w = BigUint::from(318_665_857_834_031_151_167_461_u128);
+        assert!(miller_rabin_with_bases(&w, &primes[..12]));
+        assert_eq!(
+            check_primality_with_bases(&w, &primes),
+            Primality::Composite {
+                witness: BigUint::from(41u8)
+            }
+        );
+        let prime = BigUint::from(18_446_744_073_709_551_557_u64);
+        assert_eq!(
+            check_primality_with_bases(&prime, &primes),
+            Primality::ProbablyPrime { rounds: 13 }
+        );
+        // bases congruent to 0 or -1 are skipped
+        let bases = [
+            prime.clone(),
+            &prime - 1u8,
+            &prime * 2u8,
+            BigUint::from(2u8),
+        ];
+        assert_eq!(
+            check_primality_with_bases(&prime, &bases),
+            Primality::ProbablyPrime { rounds: 1 }
+        );
+        // no round is run when all bases are skipped
+        let bases = [w.clone(), &w - 1u8, &w + 1u8];
+        assert_eq!(
+            check_primality_with_bases(&w, &bases),
+            Primality::ProbablyPrime { rounds: 0 }
+        );
+        assert_eq!(
+            check_primality_with_bases(&w, &[]),
+            Primality::ProbablyPrime { rounds: 0 }
+        );
+    }
+
+    #[test]
+    fn squaring_reaches_minus_one_after_several_steps() {
+        // step 4.5 must square repeatedly: for the primes below, w - 1 = 2^a * m and
+        // base 2, 2^m and 2^(2m) are not -1, and -1 comes only after two or three squarings
+        for (w, steps) in [(1_000_000_000_177_u64, 2), (1_000_000_000_609, 3)] {
+            let w = BigUint::from(w);
+            let w_minus_1 = &w - 1u8;
+            let a = w_minus_1.trailing_zeros().unwrap();
+            let mut z = BigUint::from(2u8).modpow(&(&w_minus_1 >> a), &w);
+            for _ in 0..steps {
+                assert_ne!(z, w_minus_1);
+                z = &z * &z % &w;
+            }
+            assert_eq!(z, w_minus_1);
+            assert!(miller_rabin_with_bases(&w, &[BigUint::from(2u8)]));
+        }
+    }
+
+    #[cfg(feature = "std")]
     #[test]
     fn test_is_probable_prime_with_small_numbers() {
         assert!(!is_probable_prime(&BigUint::from(0u8), 40));
@@ -214,4 +672,26 @@ mod tests {
         assert!(is_probable_prime(&BigUint::from(3u8), 40));
         assert!(!is_probable_prime(&BigUint::from(4u8), 40));
     }
+
+    #[test]
+    fn trial_division_by_prime_groups() {
+        for w in 0u32..20_000 {
+            let expected = if w < 2 {
+                Primality::NotPrime
+            } else if is_prime_u32(w) {
+                Primality::ProvenPrime
+            } else {
+                let p = (2..w).find(|p| w % p == 0).unwrap();
+                Primality::CompositeWithFactor(BigUint::from(p))
+            };
+            assert_eq!(trial_division(&BigUint::from(w)), Some(expected), "w = {w}");
+        }
+        // 9973 is the largest prime for trial division
+        let w = BigUint::from(9973u32) * BigUint::from(1_000_000_007_u64);
+        assert_eq!(
+            check_primality_with_rng(&w, 1, &mut seeded_rng()),
+            Primality::CompositeWithFactor(BigUint::from(9973u32))
+        );
+        assert_eq!(trial_division(&BigUint::from(100_000_007_u64)), None);
+    }
 }
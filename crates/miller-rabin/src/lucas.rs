@@ -0,0 +1,411 @@
+//! Lucas sequences and the Lucas probable prime tests
+//!
+//! ## References
+//!
+//! - R. Baillie and S. S. Wagstaff, Jr., "Lucas Pseudoprimes", Math. Comp. 35 (1980)
+//! - J. Grantham, "Frobenius pseudoprimes", Math. Comp. 70 (2001) (the extra strong test)
+//! - <https://en.wikipedia.org/wiki/Lucas_pseudoprime>
+
+use crate::jacobi::jacobi_bigint;
+use crate::trial_division;
+use num_bigint::{BigInt, BigUint};
+use num_integer::Integer;
+use num_traits::{One, Zero};
+
+/// Check if a BigUint is a Lucas probable prime with the parameters `(P, Q)`
+///
+/// With `D = P^2 - 4Q`, `w` is a Lucas probable prime if `U_(w - (D/w)) = 0 (mod w)`.
+/// Small numbers and numbers with a small factor are decided by trial division first,
+/// as in [`crate::is_probable_prime_with_rng`].
+///
+/// ## Params
+///
+/// - `w`: the number to be tested for primality
+/// - `p`: the parameter `P` of the Lucas sequence
+/// - `q`: the parameter `Q` of the Lucas sequence
+///
+/// ## Returns
+///
+/// - `Some(true)` if `w` is a Lucas probable prime
+/// - `Some(false)` if `w` is definitely composite
+/// - `None` if the test is not defined, because `w` divides `Q * D`
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use yoshi389111_miller_rabin::is_lucas_prp;
+///
+/// let w = BigUint::from(389_111_u64);
+/// assert_eq!(is_lucas_prp(&w, 1, -1), Some(true));
+/// ```
+pub fn is_lucas_prp(w: &BigUint, p: i64, q: i64) -> Option<bool> {
+    if let Some(result) = trial_division(w) {
+        return Some(result.is_probable_prime());
+    }
+    let params = LucasParams {
+        p,
+        q,
+        d: i128::from(p) * i128::from(p) - 4 * i128::from(q),
+    };
+    let qd = BigInt::from(params.q) * params.d;
+    let g = qd.magnitude().gcd(w);
+    if !g.is_one() {
+        return if g == *w { None } else { Some(false) };
+    }
+    Some(is_lucas_probable_prime(w, &params))
+}
+
+/// Check if a BigUint is a strong Lucas probable prime with Selfridge's parameters
+///
+/// This is the Lucas part of the Baillie-PSW test (see [`crate::is_bpsw_prime`]).
+/// Small numbers and numbers with a small factor are decided by trial division first,
+/// as in [`crate::is_probable_prime_with_rng`].
+///
+/// ## Params
+///
+/// - `w`: the number to be tested for primality
+///
+/// ## Returns
+///
+/// - `true` if `w` is a strong Lucas probable prime
+/// - `false` if `w` is definitely composite
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use yoshi389111_miller_rabin::is_strong_lucas_prp;
+///
+/// let w = BigUint::from(389_111_u64);
+/// assert!(is_strong_lucas_prp(&w));
+/// ```
+pub fn is_strong_lucas_prp(w: &BigUint) -> bool {
+    if let Some(result) = trial_division(w) {
+        return result.is_probable_prime();
+    }
+    match selfridge_params(w) {
+        Some(params) => is_strong_lucas_probable_prime(w, &params),
+        None => false,
+    }
+}
+
+/// Check if a BigUint is an extra strong Lucas probable prime
+///
+/// The parameters are `Q = 1` and the least `P >= 3` with `((P^2 - 4)/w) = -1` (Baillie).
+/// Small numbers and numbers with a small factor are decided by trial division first,
+/// as in [`crate::is_probable_prime_with_rng`].
+///
+/// ## Params
+///
+/// - `w`: the number to be tested for primality
+///
+/// ## Returns
+///
+/// - `true` if `w` is an extra strong Lucas probable prime
+/// - `false` if `w` is definitely composite
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use yoshi389111_miller_rabin::is_extra_strong_lucas_prp;
+///
+/// let w = BigUint::from(389_111_u64);
+/// assert!(is_extra_strong_lucas_prp(&w));
+/// ```
+pub fn is_extra_strong_lucas_prp(w: &BigUint) -> bool {
+    if let Some(result) = trial_division(w) {
+        return result.is_probable_prime();
+    }
+    match extra_strong_params(w) {
+        Some(params) => is_extra_strong_lucas_probable_prime(w, &params),
+        None => false,
+    }
+}
+
+/// Parameters of a Lucas sequence modulo `n`
+pub(crate) struct LucasParams {
+    /// the parameter `P`
+    pub(crate) p: i64,
+    /// the parameter `Q`
+    pub(crate) q: i64,
+    /// the discriminant `D = P^2 - 4Q`
+    pub(crate) d: i128,
+}
+
+/// Select the Lucas parameters by Selfridge's method A
+///
+/// `D` is the first element of `5, -7, 9, -11, 13, ...` for which the Jacobi symbol `(D/n)` is `-1`,
+/// with `P = 1` and `Q = (1 - D) / 4`.
+///
+/// ## Returns
+///
+/// - `Some(params)` if suitable parameters are found
+/// - `None` if `n` is proven composite during the search (a perfect square or a factor found)
+pub(crate) fn selfridge_params(n: &BigUint) -> Option<LucasParams> {
+    if is_square(n) {
+        return None;
+    }
+    let mut d: i64 = 5;
+    loop {
+        match jacobi_bigint(&BigInt::from(d), n) {
+            -1 => break,
+            0 if BigUint::from(d.unsigned_abs()) != *n => return None,
+            _ => {}
+        }
+        d = if d > 0 { -d - 2 } else { -d + 2 };
+    }
+    Some(LucasParams {
+        p: 1,
+        q: (1 - d) / 4,
+        d: i128::from(d),
+    })
+}
+
+/// Select the Lucas parameters for the extra strong test (Baillie)
+///
+/// `P` is the first element of `3, 4, 5, ...` for which the Jacobi symbol `((P^2 - 4)/n)` is `-1`,
+/// with `Q = 1`.
+///
+/// ## Returns
+///
+/// - `Some(params)` if suitable parameters are found
+/// - `None` if `n` is proven composite during the search (a perfect square or a factor found)
+pub(crate) fn extra_strong_params(n: &BigUint) -> Option<LucasParams> {
+    if is_square(n) {
+        return None;
+    }
+    let mut p: i64 = 3;
+    loop {
+        let d = p * p - 4;
+        match jacobi_bigint(&BigInt::from(d), n) {
+            -1 => break,
+            0 if BigUint::from(d.unsigned_abs()) != *n => return None,
+            _ => {}
+        }
+        p += 1;
+    }
+    Some(LucasParams {
+        p,
+        q: 1,
+        d: i128::from(p * p - 4),
+    })
+}
+
+/// Check if `n` is a Lucas probable prime for the given parameters
+///
+/// `n` is a Lucas probable prime if `U_(n - (D/n)) ≡ 0 (mod n)`.
+///
+/// `n` must be odd and coprime to `2 * Q * D`.
+pub(crate) fn is_lucas_probable_prime(n: &BigUint, params: &LucasParams) -> bool {
+    let k = if jacobi_bigint(&BigInt::from(params.d), n) < 0 {
+        n + 1u8
+    } else {
+        n - 1u8
+    };
+    let (u, _, _) = LucasSequence::new(n, params).calc(&k);
+    u.is_zero()
+}
+
+/// Check if `n` is a strong Lucas probable prime for the given parameters
+///
+/// With `n + 1 = 2^s * k` (`k` odd), `n` is a strong Lucas probable prime
+/// if `U_k ≡ 0 (mod n)` or `V_{2^r * k} ≡ 0 (mod n)` for some `0 <= r < s`.
+///
+/// `n` must be odd and coprime to `2 * Q * D`.
+pub(crate) fn is_strong_lucas_probable_prime(n: &BigUint, params: &LucasParams) -> bool {
+    let n_plus_1 = n + 1u8;
+    let s = n_plus_1.trailing_zeros().expect("n + 1 is not zero");
+    let k = &n_plus_1 >> s;
+
+    let seq = LucasSequence::new(n, params);
+    let (u, mut v, mut qk) = seq.calc(&k);
+    if u.is_zero() || v.is_zero() {
+        return true;
+    }
+    for _ in 1..s {
+        v = seq.double_v(&v, &qk);
+        if v.is_zero() {
+            return true;
+        }
+        qk = seq.mul(&qk, &qk);
+    }
+    false
+}
+
+/// Check if `n` is an extra strong Lucas probable prime for the given parameters
+///
+/// With `n + 1 = 2^s * k` (`k` odd), `n` is an extra strong Lucas probable prime
+/// if `U_k ≡ 0` and `V_k ≡ ±2 (mod n)`, or `V_{2^r * k} ≡ 0 (mod n)` for some `0 <= r < s - 1`.
+///
+/// `Q` must be one, and `n` must be odd and coprime to `2 * D`.
+pub(crate) fn is_extra_strong_lucas_probable_prime(n: &BigUint, params: &LucasParams) -> bool {
+    let n_plus_1 = n + 1u8;
+    let s = n_plus_1.trailing_zeros().expect("n + 1 is not zero");
+    let k = &n_plus_1 >> s;
+
+    let seq = LucasSequence::new(n, params);
+    let (u, mut v, qk) = seq.calc(&k);
+    let two = BigUint::from(2u8);
+    if u.is_zero() && (v == two || v == n - &two) {
+        return true;
+    }
+    for _ in 0..s.saturating_sub(1) {
+        if v.is_zero() {
+            return true;
+        }
+        v = seq.double_v(&v, &qk);
+    }
+    false
+}
+
+/// Lucas sequences `U_k(P, Q)` and `V_k(P, Q)` modulo `n`
+pub(crate) struct LucasSequence<'a> {
+    n: &'a BigUint,
+    p: BigUint,
+    q: BigUint,
+    d: BigUint,
+}
+
+impl<'a> LucasSequence<'a> {
+    /// Create a new sequence for the odd modulus `n`
+    pub(crate) fn new(n: &'a BigUint, params: &LucasParams) -> Self {
+        Self {
+            n,
+            p: signed_mod(params.p, n),
+            q: signed_mod(params.q, n),
+            d: signed_mod(params.d, n),
+        }
+    }
+
+    pub(crate) fn mul(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a * b % self.n
+    }
+
+    fn add(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        (a + b) % self.n
+    }
+
+    fn sub(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        if a >= b { a - b } else { self.n - b + a }
+    }
+
+    /// Calculate `a / 2 mod n`
+    fn half(&self, a: BigUint) -> BigUint {
+        if a.bit(0) { (a + self.n) >> 1 } else { a >> 1 }
+    }
+
+    /// Calculate `V_{2k} = V_k^2 - 2Q^k` from `V_k` and `Q^k`
+    pub(crate) fn double_v(&self, v: &BigUint, qk: &BigUint) -> BigUint {
+        self.sub(&self.mul(v, v), &self.add(qk, qk))
+    }
+
+    /// Calculate `(U_k, V_k, Q^k)` modulo `n` by the binary method
+    pub(crate) fn calc(&self, k: &BigUint) -> (BigUint, BigUint, BigUint) {
+        if k.is_zero() {
+            let two = BigUint::from(2u8) % self.n;
+            return (BigUint::zero(), two, BigUint::from(1u8));
+        }
+        let mut u = BigUint::from(1u8);
+        let mut v = self.p.clone();
+        let mut qk = self.q.clone();
+        for i in (0..k.bits() - 1).rev() {
+            // (U_{2j}, V_{2j}) from (U_j, V_j)
+            u = self.mul(&u, &v);
+            v = self.double_v(&v, &qk);
+            qk = self.mul(&qk, &qk);
+            if k.bit(i) {
+                // (U_{j+1}, V_{j+1}) from (U_j, V_j)
+                let u_next = self.half(self.add(&self.mul(&self.p, &u), &v));
+                v = self.half(self.add(&self.mul(&self.d, &u), &self.mul(&self.p, &v)));
+                u = u_next;
+                qk = self.mul(&qk, &self.q);
+            }
+        }
+        (u, v, qk)
+    }
+}
+
+/// Convert a signed integer into `[0, n)`
+fn signed_mod(x: impl Into<i128>, n: &BigUint) -> BigUint {
+    let x = x.into();
+    let r = BigUint::from(x.unsigned_abs()) % n;
+    if x < 0 && !r.is_zero() { n - r } else { r }
+}
+
+/// Check if `n` is a perfect square
+fn is_square(n: &BigUint) -> bool {
+    let root = n.sqrt();
+    &root * &root == *n
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lucas_sequence_matches_fibonacci() {
+        // U_k(1, -1) are the Fibonacci numbers, V_k(1, -1) are the Lucas numbers
+        let n = BigUint::from(1_000_003u32);
+        let params = LucasParams { p: 1, q: -1, d: 5 };
+        let seq = LucasSequence::new(&n, &params);
+        let (u, v, _) = seq.calc(&BigUint::from(20u8));
+        assert_eq!(u, BigUint::from(6765u32));
+        assert_eq!(v, BigUint::from(15127u32));
+    }
+
+    #[test]
+    fn strong_lucas_pseudoprimes_pass() {
+        // the smallest strong Lucas pseudoprimes with Selfridge's parameters
+        for n in [5459u32, 5777, 10877, 16109, 18971] {
+            let n = BigUint::from(n);
+            let params = selfridge_params(&n).unwrap();
+            assert!(is_strong_lucas_probable_prime(&n, &params));
+        }
+    }
+
+    #[test]
+    fn lucas_pseudoprimes_pass() {
+        // the smallest extra strong Lucas pseudoprimes
+        for n in [989u32, 3239, 5777, 10877, 27971, 29681, 30739, 31631, 39059] {
+            let n = BigUint::from(n);
+            let params = extra_strong_params(&n).unwrap();
+            assert!(is_extra_strong_lucas_probable_prime(&n, &params));
+        }
+        // 5459 is a strong Lucas pseudoprime, but not an extra strong one
+        let n = BigUint::from(5459u32);
+        assert!(!is_extra_strong_lucas_probable_prime(
+            &n,
+            &extra_strong_params(&n).unwrap()
+        ));
+
+        // the smallest Fibonacci pseudoprimes, with (P, Q) = (1, -1)
+        let params = LucasParams { p: 1, q: -1, d: 5 };
+        for n in [323u32, 377, 1891, 3827, 4181, 5777, 6601, 6721] {
+            assert!(is_lucas_probable_prime(&BigUint::from(n), &params));
+        }
+        assert!(!is_lucas_probable_prime(&BigUint::from(325u32), &params));
+    }
+
+    #[test]
+    fn lucas_prp_with_trial_division() {
+        let m127 = (BigUint::one() << 127) - 1u8;
+        let p = BigUint::from(18_446_744_073_709_551_557_u64);
+        for w in [&m127, &p] {
+            assert!(is_strong_lucas_prp(w));
+            assert!(is_extra_strong_lucas_prp(w));
+            assert_eq!(is_lucas_prp(w, 1, -1), Some(true));
+            assert_eq!(is_lucas_prp(w, 3, -1), Some(true));
+        }
+        let composite = &m127 * &p;
+        assert!(!is_strong_lucas_prp(&composite));
+        assert!(!is_extra_strong_lucas_prp(&(&p * &p)));
+        assert_eq!(is_lucas_prp(&composite, 1, -1), Some(false));
+        // pseudoprimes with small factors are rejected by trial division
+        assert!(!is_strong_lucas_prp(&BigUint::from(5459u32)));
+        // the test is not defined when `w` divides `Q * D`
+        let w = BigUint::from(1_000_000_007_u64);
+        assert_eq!(is_lucas_prp(&w, 1, 1_000_000_007), None);
+    }
+}
@@ -0,0 +1,101 @@
+//! Lucas-Lehmer test for Mersenne numbers
+//!
+//! ## References
+//!
+//! - D. H. Lehmer, "An extended theory of Lucas' functions", Ann. of Math. 31 (1930)
+//! - R. Crandall and C. Pomerance, "Prime Numbers: A Computational Perspective", Section 4.2.1
+
+use crate::small_int::is_prime_u64;
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+
+/// Check if the Mersenne number `2^p - 1` is prime using the Lucas-Lehmer test
+///
+/// `2^p - 1` is prime if and only if `s_(p-2) = 0 (mod 2^p - 1)`,
+/// where `s_0 = 4` and `s_(i+1) = s_i^2 - 2`.
+/// The reduction modulo `2^p - 1` is done by shifts and additions, without division.
+///
+/// ## Notes
+///
+/// The test is deterministic: the result is a proof, not a probable prime.
+/// `2^p - 1` is composite when `p` is composite, so the exponent is checked first.
+///
+/// ## Params
+///
+/// - `p`: the exponent of the Mersenne number
+///
+/// ## Returns
+///
+/// - `true` if `2^p - 1` is prime
+/// - `false` if `2^p - 1` is composite, or `p` is less than 2
+///
+/// ## Example
+///
+/// ```rust
+/// use yoshi389111_miller_rabin::is_mersenne_prime;
+///
+/// assert!(is_mersenne_prime(127));
+/// assert!(!is_mersenne_prime(67));
+/// ```
+pub fn is_mersenne_prime(p: u64) -> bool {
+    if !is_prime_u64(p) {
+        return false;
+    }
+    if p == 2 {
+        // 2^2 - 1 = 3
+        return true;
+    }
+
+    let modulus = (BigUint::one() << p) - 1u8;
+    let two = BigUint::from(2u8);
+    let mut s = BigUint::from(4u8);
+    for _ in 0..p - 2 {
+        s = reduce_mersenne(&s * &s, p, &modulus);
+        s = if s < two { s + &modulus - 2u8 } else { s - 2u8 };
+    }
+    s.is_zero()
+}
+
+/// Reduce `x` modulo `2^p - 1`, using `2^p = 1 (mod 2^p - 1)`
+///
+/// The result is in `0..2^p - 1`.
+fn reduce_mersenne(mut x: BigUint, p: u64, modulus: &BigUint) -> BigUint {
+    while x.bits() > p {
+        x = (&x & modulus) + (&x >> p);
+    }
+    if x == *modulus { BigUint::zero() } else { x }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Exponents of the Mersenne primes below `2^1300`
+    const MERSENNE_EXPONENTS: [u64; 15] =
+        [2, 3, 5, 7, 13, 17, 19, 31, 61, 89, 107, 127, 521, 607, 1279];
+
+    #[test]
+    fn is_mersenne_prime_with_prime_exponents() {
+        for p in 0..1300 {
+            assert_eq!(
+                is_mersenne_prime(p),
+                MERSENNE_EXPONENTS.contains(&p),
+                "p = {p}"
+            );
+        }
+    }
+
+    #[test]
+    fn reduce_mersenne_matches_remainder() {
+        let p: u64 = 89;
+        let modulus = (BigUint::one() << p) - 1u8;
+        for x in [
+            BigUint::zero(),
+            modulus.clone(),
+            &modulus * &modulus,
+            (BigUint::one() << 200) + 12345u32,
+        ] {
+            assert_eq!(reduce_mersenne(x.clone(), p, &modulus), x % &modulus);
+        }
+    }
+}
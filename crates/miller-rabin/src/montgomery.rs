@@ -0,0 +1,379 @@
+//! Montgomery arithmetic for a fixed odd modulus
+//!
+//! The setup for a modulus is done once, and shared by all exponentiations and squarings
+//! of the Miller-Rabin rounds for the same candidate.
+//! Numbers in Montgomery form are little-endian `u64` limbs of the same length as the modulus.
+//! Multiplication, squaring and addition run in time that depends only on the number of limbs,
+//! which the constant-time primality test relies on.
+//! The products are computed in a scratch space allocated once by the caller,
+//! so the exponentiation and the squaring loops do not allocate for each operation.
+//!
+//! ## References
+//!
+//! - P. L. Montgomery, "Modular multiplication without trial division", Math. Comp. 44 (1985)
+//! - Ç. K. Koç, T. Acar and B. S. Kaliski, "Analyzing and comparing Montgomery multiplication algorithms",
+//!   IEEE Micro 16 (1996)
+
+use alloc::vec;
+use alloc::vec::Vec;
+use num_bigint::BigUint;
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+
+/// Width of the window for exponentiation
+const WINDOW_BITS: u64 = 4;
+
+/// Montgomery context for an odd modulus `n` with `R = 2^(64 * k)`
+pub(crate) struct MontgomeryContext {
+    /// the modulus
+    modulus: BigUint,
+    /// limbs of the modulus
+    n: Vec<u64>,
+    /// `-n^-1 mod 2^64`
+    n0_inv: u64,
+    /// `R^2 mod n`
+    r2: Vec<u64>,
+    /// Montgomery form of one (`R mod n`)
+    one: Vec<u64>,
+    /// Montgomery form of `n - 1`
+    minus_one: Vec<u64>,
+}
+
+impl MontgomeryContext {
+    /// Create a new context for the odd modulus `n` (`n > 1`)
+    pub(crate) fn new(n: &BigUint) -> Self {
+        debug_assert!(n.bit(0) && n.bits() > 1);
+        let limbs = to_limbs(n, n.iter_u64_digits().len());
+        let k = limbs.len();
+        let n0_inv = neg_inverse(limbs[0]);
+
+        let r_bits = 64 * k as u64;
+        let one = (BigUint::from(1u8) << r_bits) % n;
+        let r2 = (BigUint::from(1u8) << (2 * r_bits)) % n;
+        let minus_one = n - &one;
+        Self {
+            modulus: n.clone(),
+            n: limbs,
+            n0_inv,
+            r2: to_limbs(&r2, k),
+            one: to_limbs(&one, k),
+            minus_one: to_limbs(&minus_one, k),
+        }
+    }
+
+    /// Create a new context for the odd modulus `n` (`n > 1`) in constant time
+    ///
+    /// Unlike [`Self::new`], `R mod n` and `R^2 mod n` are computed by modular doublings
+    /// instead of divisions, so the running time depends only on the number of limbs.
+    pub(crate) fn new_constant_time(n: &BigUint) -> Self {
+        let limbs = to_limbs(n, n.iter_u64_digits().len());
+        let k = limbs.len();
+        let n0_inv = neg_inverse(limbs[0]);
+
+        let mut ctx = Self {
+            modulus: n.clone(),
+            n: limbs,
+            n0_inv,
+            r2: Vec::new(),
+            one: Vec::new(),
+            minus_one: Vec::new(),
+        };
+
+        // 2^(64 * k) mod n, then 2^(128 * k) mod n
+        let mut x = vec![0u64; k];
+        x[0] = 1;
+        for _ in 0..64 * k {
+            x = ctx.add(&x, &x);
+        }
+        ctx.one = x.clone();
+        for _ in 0..64 * k {
+            x = ctx.add(&x, &x);
+        }
+        ctx.r2 = x;
+
+        let mut borrow = 0u64;
+        ctx.minus_one = ctx
+            .n
+            .iter()
+            .zip(ctx.one.iter())
+            .map(|(&n_j, &one_j)| {
+                let (x, b1) = n_j.overflowing_sub(one_j);
+                let (x, b2) = x.overflowing_sub(borrow);
+                borrow = u64::from(b1 | b2);
+                x
+            })
+            .collect();
+        ctx
+    }
+
+    /// `R^2 mod n`, which is the Montgomery form of `R`
+    pub(crate) fn r2(&self) -> &[u64] {
+        &self.r2
+    }
+
+    /// Montgomery form of one
+    pub(crate) fn one(&self) -> &[u64] {
+        &self.one
+    }
+
+    /// Montgomery form of `n - 1`
+    pub(crate) fn minus_one(&self) -> &[u64] {
+        &self.minus_one
+    }
+
+    /// Allocate the scratch space of `2k + 1` limbs used by the products
+    ///
+    /// A caller that multiplies in a loop allocates it once and passes it to
+    /// [`Self::mul_into`], [`Self::sqr_into`], [`Self::mul_assign`] and [`Self::sqr_assign`].
+    pub(crate) fn scratch(&self) -> Vec<u64> {
+        vec![0u64; 2 * self.n.len() + 1]
+    }
+
+    /// Convert a number into Montgomery form
+    ///
+    /// The reduction modulo `n` is not constant time.
+    pub(crate) fn to_mont(&self, a: &BigUint) -> Vec<u64> {
+        let a = a % &self.modulus;
+        self.mul(&to_limbs(&a, self.n.len()), &self.r2)
+    }
+
+    /// Convert a number from Montgomery form into a BigUint
+    pub(crate) fn to_biguint(&self, a: &[u64]) -> BigUint {
+        let mut one = vec![0; self.n.len()];
+        one[0] = 1;
+        from_limbs(&self.mul(a, &one))
+    }
+
+    /// Montgomery multiplication `a * b * R^-1 mod n` into a new vector
+    pub(crate) fn mul(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let mut out = vec![0u64; self.n.len()];
+        self.mul_into(&mut out, a, b, &mut self.scratch());
+        out
+    }
+
+    /// Montgomery squaring `a^2 * R^-1 mod n` into a new vector
+    pub(crate) fn sqr(&self, a: &[u64]) -> Vec<u64> {
+        let mut out = vec![0u64; self.n.len()];
+        self.sqr_into(&mut out, a, &mut self.scratch());
+        out
+    }
+
+    /// Montgomery multiplication `out = a * b * R^-1 mod n` (`a * b < n * R`), with the scratch space `t`
+    ///
+    /// The running time depends only on the number of limbs.
+    pub(crate) fn mul_into(&self, out: &mut [u64], a: &[u64], b: &[u64], t: &mut [u64]) {
+        self.product(t, a, b);
+        self.reduce(t, out);
+    }
+
+    /// Montgomery squaring `out = a^2 * R^-1 mod n`, with the scratch space `t`
+    pub(crate) fn sqr_into(&self, out: &mut [u64], a: &[u64], t: &mut [u64]) {
+        self.square(t, a);
+        self.reduce(t, out);
+    }
+
+    /// Montgomery multiplication in place `a = a * b * R^-1 mod n`, with the scratch space `t`
+    pub(crate) fn mul_assign(&self, a: &mut [u64], b: &[u64], t: &mut [u64]) {
+        self.product(t, a, b);
+        self.reduce(t, a);
+    }
+
+    /// Montgomery squaring in place `a = a^2 * R^-1 mod n`, with the scratch space `t`
+    pub(crate) fn sqr_assign(&self, a: &mut [u64], t: &mut [u64]) {
+        self.square(t, a);
+        self.reduce(t, a);
+    }
+
+    /// Calculate the `2k + 1` limbs product `t = a * b`
+    fn product(&self, t: &mut [u64], a: &[u64], b: &[u64]) {
+        let k = self.n.len();
+        t.fill(0);
+        for (i, &a_i) in a.iter().enumerate() {
+            let mut carry = 0u128;
+            for (t_j, &b_j) in t[i..i + k].iter_mut().zip(b.iter()) {
+                let sum = u128::from(*t_j) + u128::from(a_i) * u128::from(b_j) + carry;
+                *t_j = sum as u64;
+                carry = sum >> 64;
+            }
+            t[i + k] = carry as u64;
+        }
+    }
+
+    /// Calculate the `2k + 1` limbs square `t = a^2`
+    ///
+    /// Each cross product `a_i * a_j` is computed once and doubled,
+    /// which saves nearly half of the multiplications of [`Self::product`].
+    fn square(&self, t: &mut [u64], a: &[u64]) {
+        let k = self.n.len();
+        t.fill(0);
+        // cross products
+        for (i, &a_i) in a.iter().enumerate() {
+            let mut carry = 0u128;
+            for (t_j, &a_j) in t[2 * i + 1..i + k].iter_mut().zip(a[i + 1..].iter()) {
+                let sum = u128::from(*t_j) + u128::from(a_i) * u128::from(a_j) + carry;
+                *t_j = sum as u64;
+                carry = sum >> 64;
+            }
+            t[i + k] = carry as u64;
+        }
+        // double them, and add the squares
+        let mut shifted = 0u64;
+        let mut carry = 0u128;
+        for (i, &a_i) in a.iter().enumerate() {
+            let square = u128::from(a_i) * u128::from(a_i);
+            for (j, half) in [(2 * i, square as u64), (2 * i + 1, (square >> 64) as u64)] {
+                let doubled = (t[j] << 1) | shifted;
+                shifted = t[j] >> 63;
+                let sum = u128::from(doubled) + u128::from(half) + carry;
+                t[j] = sum as u64;
+                carry = sum >> 64;
+            }
+        }
+    }
+
+    /// Montgomery reduction `out = t * R^-1 mod n` of a `2k + 1` limbs number `t < n * R`
+    ///
+    /// `t` is overwritten. The running time depends only on the number of limbs.
+    fn reduce(&self, t: &mut [u64], out: &mut [u64]) {
+        let k = self.n.len();
+        let mut extra = 0u64;
+        for i in 0..k {
+            let m = t[i].wrapping_mul(self.n0_inv);
+            let mut carry = 0u128;
+            for (t_j, &n_j) in t[i..i + k].iter_mut().zip(self.n.iter()) {
+                let sum = u128::from(*t_j) + u128::from(m) * u128::from(n_j) + carry;
+                *t_j = sum as u64;
+                carry = sum >> 64;
+            }
+            let sum = u128::from(t[i + k]) + carry + u128::from(extra);
+            t[i + k] = sum as u64;
+            extra = (sum >> 64) as u64;
+        }
+
+        out.copy_from_slice(&t[k..2 * k]);
+        self.subtract_modulus_if_needed(out, extra.ct_eq(&1));
+    }
+
+    /// Subtract `n` from `t < 2n` if `t >= n`, where `overflow` is the bit above `t`
+    fn subtract_modulus_if_needed(&self, t: &mut [u64], overflow: Choice) {
+        // the final borrow of `t - n` tells whether `t >= n`
+        let mut borrow = 0u64;
+        for (&t_j, &n_j) in t.iter().zip(self.n.iter()) {
+            let (x, b1) = t_j.overflowing_sub(n_j);
+            let (_, b2) = x.overflowing_sub(borrow);
+            borrow = u64::from(b1 | b2);
+        }
+        let use_diff = overflow | borrow.ct_eq(&0);
+        let mut borrow = 0u64;
+        for (t_j, &n_j) in t.iter_mut().zip(self.n.iter()) {
+            let (x, b1) = t_j.overflowing_sub(n_j);
+            let (x, b2) = x.overflowing_sub(borrow);
+            borrow = u64::from(b1 | b2);
+            t_j.conditional_assign(&x, use_diff);
+        }
+    }
+
+    /// Modular addition `a + b mod n` in constant time into a new vector
+    pub(crate) fn add(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let mut sum = a.to_vec();
+        self.add_assign(&mut sum, b);
+        sum
+    }
+
+    /// Modular addition in place `a = a + b mod n` in constant time
+    pub(crate) fn add_assign(&self, a: &mut [u64], b: &[u64]) {
+        let mut carry = 0u64;
+        for (a_j, &b_j) in a.iter_mut().zip(b.iter()) {
+            let (x, c1) = a_j.overflowing_add(b_j);
+            let (x, c2) = x.overflowing_add(carry);
+            *a_j = x;
+            carry = u64::from(c1 | c2);
+        }
+        self.subtract_modulus_if_needed(a, carry.ct_eq(&1));
+    }
+
+    /// Exponentiation `base^exp` in Montgomery form (fixed window method)
+    pub(crate) fn pow(&self, base: &[u64], exp: &BigUint) -> Vec<u64> {
+        let mut t = self.scratch();
+        // table[i] = base^i
+        let mut table = Vec::with_capacity(1 << WINDOW_BITS);
+        table.push(self.one.clone());
+        for i in 1..(1 << WINDOW_BITS) {
+            let mut next = vec![0u64; self.n.len()];
+            self.mul_into(&mut next, &table[i - 1], base, &mut t);
+            table.push(next);
+        }
+
+        let mut result = self.one.clone();
+        let windows = exp.bits().div_ceil(WINDOW_BITS);
+        for w in (0..windows).rev() {
+            for _ in 0..WINDOW_BITS {
+                self.sqr_assign(&mut result, &mut t);
+            }
+            let index = (0..WINDOW_BITS)
+                .filter(|&i| exp.bit(w * WINDOW_BITS + i))
+                .fold(0, |acc, i| acc | (1 << i));
+            if index != 0 {
+                self.mul_assign(&mut result, &table[index], &mut t);
+            }
+        }
+        result
+    }
+}
+
+/// Calculate `-x^-1 mod 2^64` for an odd `x`
+fn neg_inverse(x: u64) -> u64 {
+    // Newton's iteration: each step doubles the number of correct low bits
+    let mut inv = x;
+    for _ in 0..5 {
+        inv = inv.wrapping_mul(2u64.wrapping_sub(x.wrapping_mul(inv)));
+    }
+    inv.wrapping_neg()
+}
+
+/// Convert a BigUint into `k` little-endian limbs
+fn to_limbs(a: &BigUint, k: usize) -> Vec<u64> {
+    let mut limbs: Vec<u64> = a.iter_u64_digits().collect();
+    limbs.resize(k, 0);
+    limbs
+}
+
+/// Convert little-endian limbs into a BigUint
+fn from_limbs(a: &[u64]) -> BigUint {
+    BigUint::new(
+        a.iter()
+            .flat_map(|&limb| [limb as u32, (limb >> 32) as u32])
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::seeded_rng;
+    use num_bigint::RandBigInt;
+
+    #[test]
+    fn pow_matches_modpow() {
+        let mut rng = seeded_rng();
+        for bits in [2, 63, 64, 65, 128, 500, 1024] {
+            for _ in 0..10 {
+                let n =
+                    rng.gen_biguint(bits) | BigUint::from(1u8) | (BigUint::from(1u8) << (bits - 1));
+                let base = rng.gen_biguint(bits + 10);
+                let exp = rng.gen_biguint(bits);
+                let ctx = MontgomeryContext::new(&n);
+                let result = ctx.to_biguint(&ctx.pow(&ctx.to_mont(&base), &exp));
+                assert_eq!(result, base.modpow(&exp, &n), "n = {n}");
+            }
+        }
+    }
+
+    #[test]
+    fn one_and_minus_one_are_in_montgomery_form() {
+        let n = BigUint::from(18_446_744_073_709_551_557_u64) * 1_000_003u32;
+        let ctx = MontgomeryContext::new(&n);
+        assert_eq!(ctx.to_biguint(ctx.one()), BigUint::from(1u8));
+        assert_eq!(ctx.to_biguint(ctx.minus_one()), &n - 1u8);
+        assert_eq!(ctx.to_mont(&(&n - 1u8)), ctx.minus_one());
+    }
+}
@@ -0,0 +1,168 @@
+//! Parallel Miller-Rabin test with rayon
+//!
+//! With the `rayon` feature, the rounds of a single candidate run on the rayon thread pool,
+//! and the remaining rounds are cancelled as soon as any round finds a witness.
+//! The bases are drawn from the caller's random number generator before the rounds start,
+//! so the generator does not need to be shared between threads.
+//! Batches of candidates are tested in parallel as well.
+//!
+//! ## References
+//!
+//! - <https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.186-5.pdf>
+//!   - Appendix B.3.1 Miller-Rabin Probabilistic Primality Test
+
+use crate::primality::Primality;
+use crate::proth::{proth_form, proth_test};
+use crate::riesel::{riesel_form, riesel_test};
+use crate::{TWO, check_primality, round_tester, trial_division};
+use num_bigint::{BigUint, RandBigInt};
+use rayon::prelude::*;
+
+/// Check the primality of a BigUint with the Miller-Rabin rounds running in parallel, with a detailed result
+///
+/// The result is the same as [`crate::check_primality_with_rng`],
+/// except that the witness of a composite number is any witness among the bases,
+/// not necessarily the first one drawn.
+///
+/// ## Params
+///
+/// - `w`: the number to be tested for primality
+/// - `iter`: number of iterations
+/// - `rng`: random number generator
+///
+/// ## Returns
+///
+/// - the detailed result (see [`crate::check_primality_with_rng`])
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use rand::rngs::OsRng;
+/// use yoshi389111_miller_rabin::{Primality, check_primality_par_with_rng};
+///
+/// // product of the Mersenne primes 2^127 - 1 and 2^521 - 1
+/// let w = ((BigUint::from(1u8) << 127) - 1u8) * ((BigUint::from(1u8) << 521) - 1u8);
+/// let mut rng = OsRng;
+/// let result = check_primality_par_with_rng(&w, 64, &mut rng);
+/// assert!(matches!(result, Primality::Composite { .. }));
+/// ```
+pub fn check_primality_par_with_rng<R: rand::Rng + ?Sized>(
+    w: &BigUint,
+    iter: usize,
+    rng: &mut R,
+) -> Primality {
+    if let Some(result) = trial_division(w) {
+        return result;
+    }
+    if let Some((k, n)) = proth_form(w) {
+        return proth_test(w, &k, n);
+    }
+    if let Some((k, n)) = riesel_form(w) {
+        return riesel_test(w, &k, n);
+    }
+
+    let w_minus_1 = w - 1u8;
+    // step 4.1 - 4.2 for all rounds
+    let bases: Vec<BigUint> = (0..iter)
+        .map(|_| rng.gen_biguint_range(&TWO, &w_minus_1))
+        .collect();
+    // step 1 - 3.
+    let round = round_tester(w);
+    // step 4.3 - 4.7, stopping the other rounds when a witness is found
+    match bases.into_par_iter().find_any(|b| !round(b)) {
+        Some(witness) => Primality::Composite { witness },
+        None => Primality::ProbablyPrime { rounds: iter }, // step 5.
+    }
+}
+
+/// Check if a BigUint is probably prime with the Miller-Rabin rounds running in parallel,
+/// with OS random number generator
+///
+/// ## Params
+///
+/// - `w`: the number to be tested for primality
+/// - `iter`: number of iterations
+///
+/// ## Returns
+///
+/// - `true` if `w` is probably prime
+/// - `false` if `w` is definitely composite
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use yoshi389111_miller_rabin::is_probable_prime_par;
+///
+/// let w = (BigUint::from(1u8) << 521) - 1u8;
+/// assert!(is_probable_prime_par(&w, 64));
+/// ```
+pub fn is_probable_prime_par(w: &BigUint, iter: usize) -> bool {
+    check_primality_par_with_rng(w, iter, &mut rand::rngs::OsRng).is_probable_prime()
+}
+
+/// Select the probable primes from a list of BigUint candidates, testing the candidates in parallel
+///
+/// Each candidate is tested as in [`crate::check_primality`].
+///
+/// ## Params
+///
+/// - `candidates`: the numbers to be tested for primality
+/// - `rounds`: number of Miller-Rabin rounds for each candidate that passes trial division
+///
+/// ## Returns
+///
+/// - the candidates that are probably prime, in the original order
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use yoshi389111_miller_rabin::filter_probable_primes_par;
+///
+/// let candidates: Vec<BigUint> = (389_100_u32..389_116).map(BigUint::from).collect();
+/// assert_eq!(filter_probable_primes_par(&candidates, 40), vec![BigUint::from(389_111_u32)]);
+/// ```
+pub fn filter_probable_primes_par(candidates: &[BigUint], rounds: usize) -> Vec<BigUint> {
+    candidates
+        .par_iter()
+        .filter(|w| check_primality(w, rounds).is_probable_prime())
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter_probable_primes;
+    use crate::tests::seeded_rng;
+
+    #[test]
+    fn parallel_rounds_find_witnesses() {
+        let mut rng = seeded_rng();
+        // strong pseudoprime to the bases 2 to 37 (Sorenson-Webster)
+        let psi12 = BigUint::from(318_665_857_834_031_151_167_461_u128);
+        match check_primality_par_with_rng(&psi12, 64, &mut rng) {
+            Primality::Composite { witness } => {
+                assert!(!crate::miller_rabin_with_bases(&psi12, &[witness]));
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+        let p = (BigUint::from(1u8) << 200) + 235u8;
+        assert_eq!(
+            check_primality_par_with_rng(&p, 16, &mut rng),
+            Primality::ProbablyPrime { rounds: 16 }
+        );
+    }
+
+    #[test]
+    fn parallel_batch_matches_sequential_batch() {
+        let base = BigUint::from(1u8) << 256;
+        let candidates: Vec<BigUint> = (0u32..1000).map(|i| &base + i).collect();
+        assert_eq!(
+            filter_probable_primes_par(&candidates, 10),
+            filter_probable_primes(&candidates, 10)
+        );
+    }
+}
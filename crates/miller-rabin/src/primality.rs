@@ -0,0 +1,39 @@
+//! Detailed result of a primality test
+
+use num_bigint::BigUint;
+
+/// Detailed result of a primality test
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Primality {
+    /// The number is zero or one, which is neither prime nor composite
+    NotPrime,
+    /// The number is composite, and `witness` is a base that proves it
+    Composite {
+        /// a base to which the number is not a strong probable prime
+        witness: BigUint,
+    },
+    /// The number is composite, with a nontrivial factor
+    CompositeWithFactor(BigUint),
+    /// The number passed all rounds of the probabilistic test
+    ProbablyPrime {
+        /// number of rounds performed
+        rounds: usize,
+    },
+    /// The number is proven prime by trial division or a test for its special form
+    ProvenPrime,
+}
+
+impl Primality {
+    /// Check if the result means the number is (probably) prime
+    ///
+    /// ## Returns
+    ///
+    /// - `true` for `ProbablyPrime` and `ProvenPrime`
+    /// - `false` otherwise
+    pub fn is_probable_prime(&self) -> bool {
+        matches!(
+            self,
+            Primality::ProbablyPrime { .. } | Primality::ProvenPrime
+        )
+    }
+}
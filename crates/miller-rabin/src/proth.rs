@@ -0,0 +1,212 @@
+//! Proth's theorem and Pépin's test for numbers of the form `k * 2^n + 1`
+//!
+//! A Proth number `N = k * 2^n + 1` with an odd `k < 2^n` is prime if and only if
+//! `a^((N - 1) / 2) = -1 (mod N)` for a quadratic non-residue `a` modulo `N`.
+//! Pépin's test is the case of Fermat numbers `F_m = 2^(2^m) + 1`, with `k = 1`.
+//!
+//! ## References
+//!
+//! - F. Proth, "Théorèmes sur les nombres premiers", C. R. Acad. Sci. Paris 87 (1878)
+//! - R. Crandall and C. Pomerance, "Prime Numbers: A Computational Perspective", Section 4.1.3
+
+use crate::jacobi::jacobi_bigint;
+use crate::primality::Primality;
+use crate::special_form::SpecialModulus;
+use crate::trial_division;
+use num_bigint::{BigInt, BigUint};
+use num_traits::One;
+
+/// Check if `k * 2^n + 1` is prime using Proth's theorem
+///
+/// Factors of two in `k` are moved to the exponent,
+/// so that `k = 6, n = 3` is the same as `k = 3, n = 4`.
+/// The reduction modulo `k * 2^n + 1` is done by shifts and a division by `k`,
+/// which is fast when `k` is much smaller than `2^n`.
+///
+/// ## Notes
+///
+/// The test is deterministic: the result is a proof, not a probable prime.
+///
+/// ## Params
+///
+/// - `k`: the multiplier
+/// - `n`: the exponent of two
+///
+/// ## Returns
+///
+/// - `Some(true)` if `k * 2^n + 1` is prime
+/// - `Some(false)` if `k * 2^n + 1` is composite
+/// - `None` if `k * 2^n + 1` is not a Proth number (`k` is zero, or the odd part of `k` is not less than `2^n`)
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use yoshi389111_miller_rabin::is_proth_prime;
+///
+/// // 3 * 2^189 + 1 is prime
+/// assert_eq!(is_proth_prime(&BigUint::from(3u8), 189), Some(true));
+/// assert_eq!(is_proth_prime(&BigUint::from(3u8), 190), Some(false));
+/// assert_eq!(is_proth_prime(&BigUint::from(17u8), 4), None);
+/// ```
+pub fn is_proth_prime(k: &BigUint, n: u64) -> Option<bool> {
+    let s = k.trailing_zeros()?;
+    let k = k >> s;
+    let n = n + s;
+    if k.bits() > n {
+        return None;
+    }
+    let w = (&k << n) + 1u8;
+    if let Some(result) = trial_division(&w) {
+        return Some(result.is_probable_prime());
+    }
+    Some(proth_test(&w, &k, n).is_probable_prime())
+}
+
+/// Check if the Fermat number `2^(2^m) + 1` is prime using Pépin's test
+///
+/// ## Notes
+///
+/// The test is deterministic: the result is a proof, not a probable prime.
+/// `F_m` has `2^m + 1` bits, so `m` is limited by the memory and the time in practice.
+///
+/// ## Panics
+///
+/// Panics if `m` is 64 or more, since the exponent `2^m` does not fit in a `u64`.
+///
+/// ## Params
+///
+/// - `m`: the index of the Fermat number (less than 64)
+///
+/// ## Returns
+///
+/// - `true` if `2^(2^m) + 1` is prime
+/// - `false` if `2^(2^m) + 1` is composite
+///
+/// ## Example
+///
+/// ```rust
+/// use yoshi389111_miller_rabin::is_fermat_prime;
+///
+/// // 2^16 + 1 = 65537 is prime
+/// assert!(is_fermat_prime(4));
+/// // 2^32 + 1 = 641 * 6700417
+/// assert!(!is_fermat_prime(5));
+/// ```
+pub fn is_fermat_prime(m: u32) -> bool {
+    if m == 0 {
+        // 2^1 + 1 = 3
+        return true;
+    }
+    let n = 1u64.checked_shl(m).expect("m must be less than 64");
+    is_proth_prime(&BigUint::one(), n).expect("Fermat numbers are Proth numbers")
+}
+
+/// Detect a Proth number `w = k * 2^n + 1` with an odd `k < 2^n`
+///
+/// ## Returns
+///
+/// - `Some((k, n))` if `w` is a Proth number
+/// - `None` otherwise
+pub(crate) fn proth_form(w: &BigUint) -> Option<(BigUint, u64)> {
+    let w_minus_1 = w - 1u8;
+    let n = w_minus_1.trailing_zeros()?;
+    let k = w_minus_1 >> n;
+    (k.bits() <= n).then_some((k, n))
+}
+
+/// Run Proth's test on `w = k * 2^n + 1`, after trial division
+///
+/// ## Returns
+///
+/// - `Primality::ProvenPrime` if `w` is prime
+/// - `Primality::Composite` with a quadratic non-residue `a` such that `a^((w - 1) / 2) != -1`
+/// - `Primality::CompositeWithFactor` if a factor of `w` is found
+pub(crate) fn proth_test(w: &BigUint, k: &BigUint, n: u64) -> Primality {
+    // a perfect square has no quadratic non-residue to search for
+    let root = w.sqrt();
+    if &root * &root == *w {
+        return Primality::CompositeWithFactor(root);
+    }
+
+    let modulus = SpecialModulus::plus_one(k, n);
+    let w_minus_1 = w - 1u8;
+    for a in yoshi389111_prime_iter::new::<u64>() {
+        match jacobi_bigint(&BigInt::from(a), w) {
+            0 => return Primality::CompositeWithFactor(BigUint::from(a)),
+            1 => continue,
+            _ => {}
+        }
+        // a^((w - 1) / 2) = (a^k)^(2^(n - 1))
+        let a = BigUint::from(a);
+        let mut z = modulus.pow(&a, k);
+        for _ in 1..n {
+            z = modulus.mul(&z, &z);
+        }
+        return if z == w_minus_1 {
+            Primality::ProvenPrime
+        } else {
+            Primality::Composite { witness: a }
+        };
+    }
+    unreachable!("a number that is not a perfect square has a quadratic non-residue")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::is_bpsw_prime;
+    use num_traits::Zero;
+
+    #[test]
+    fn is_proth_prime_with_known_exponents() {
+        // exponents n < 600 for which 3 * 2^n + 1 is prime
+        let exponents = [
+            2, 5, 6, 8, 12, 18, 30, 36, 41, 66, 189, 201, 209, 276, 353, 408, 438, 534,
+        ];
+        let three = BigUint::from(3u8);
+        for n in 2..600 {
+            assert_eq!(
+                is_proth_prime(&three, n),
+                Some(exponents.contains(&n)),
+                "n = {n}"
+            );
+        }
+    }
+
+    #[test]
+    fn is_proth_prime_matches_bpsw() {
+        for k in (1u32..200).step_by(2) {
+            for n in [8, 13, 32, 64, 100] {
+                let w = (BigUint::from(k) << n) + 1u8;
+                assert_eq!(
+                    is_proth_prime(&BigUint::from(k), n),
+                    Some(is_bpsw_prime(&w)),
+                    "k = {k}, n = {n}"
+                );
+            }
+        }
+        assert_eq!(is_proth_prime(&BigUint::zero(), 10), None);
+        assert_eq!(is_proth_prime(&BigUint::from(1025u32), 10), None);
+        // 12 * 2^10 + 1 = 3 * 2^12 + 1
+        assert_eq!(is_proth_prime(&BigUint::from(12u8), 10), Some(true));
+    }
+
+    #[test]
+    fn is_fermat_prime_with_pepin() {
+        for m in 0..12 {
+            assert_eq!(is_fermat_prime(m), m <= 4, "m = {m}");
+        }
+    }
+
+    #[test]
+    fn proth_form_detection() {
+        let w = (BigUint::from(3u8) << 189) + 1u8;
+        assert_eq!(proth_form(&w), Some((BigUint::from(3u8), 189)));
+        let w = (BigUint::from(1025u32) << 10) + 1u8;
+        assert_eq!(proth_form(&w), None);
+        // 2^61 - 1 = 2 * (2^60 - 1) + 1
+        let w = (BigUint::one() << 61) - 1u8;
+        assert_eq!(proth_form(&w), None);
+    }
+}
@@ -0,0 +1,225 @@
+//! Lucas-Lehmer-Riesel test for numbers of the form `k * 2^n - 1`
+//!
+//! For `N = k * 2^n - 1` with an odd `k < 2^n`, let `u_0 = V_k(P, 1) mod N`
+//! and `u_(i+1) = u_i^2 - 2 mod N`. Then `N` is prime if and only if `u_(n-2) = 0`,
+//! provided that the Jacobi symbols `((P - 2)/N) = 1` and `((P + 2)/N) = -1`.
+//! The seed `P` is searched from three upwards, which also covers `k` divisible by three,
+//! where the classical seeds `P = 4` or `V_k(4, 1)` do not apply.
+//!
+//! ## References
+//!
+//! - H. Riesel, "Lucasian criteria for the primality of N = h 2^n - 1", Math. Comp. 23 (1969)
+//! - Ø. J. Rödseth, "A note on primality tests for N = h 2^n - 1", BIT 34 (1994)
+
+use crate::jacobi::jacobi_bigint;
+use crate::primality::Primality;
+use crate::special_form::SpecialModulus;
+use crate::{round_tester, trial_division};
+use num_bigint::{BigInt, BigUint};
+use num_integer::Integer;
+use num_traits::Zero;
+
+/// Check if `k * 2^n - 1` is prime using the Lucas-Lehmer-Riesel test
+///
+/// Factors of two in `k` are moved to the exponent,
+/// so that `k = 6, n = 3` is the same as `k = 3, n = 4`.
+/// The reduction modulo `k * 2^n - 1` is done by shifts and a division by `k`,
+/// which is fast when `k` is much smaller than `2^n`.
+///
+/// ## Notes
+///
+/// The test is deterministic: the result is a proof, not a probable prime.
+/// For `k = 1`, it is the Lucas-Lehmer test (see [`crate::is_mersenne_prime`]).
+///
+/// ## Params
+///
+/// - `k`: the multiplier
+/// - `n`: the exponent of two
+///
+/// ## Returns
+///
+/// - `Some(true)` if `k * 2^n - 1` is prime
+/// - `Some(false)` if `k * 2^n - 1` is composite
+/// - `None` if `k` is zero, or the odd part of `k` is not less than `2^n`
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use yoshi389111_miller_rabin::is_riesel_prime;
+///
+/// // 3 * 2^206 - 1 is prime
+/// assert_eq!(is_riesel_prime(&BigUint::from(3u8), 206), Some(true));
+/// assert_eq!(is_riesel_prime(&BigUint::from(3u8), 207), Some(false));
+/// assert_eq!(is_riesel_prime(&BigUint::from(17u8), 4), None);
+/// ```
+pub fn is_riesel_prime(k: &BigUint, n: u64) -> Option<bool> {
+    let s = k.trailing_zeros()?;
+    let k = k >> s;
+    let n = n + s;
+    if k.bits() > n {
+        return None;
+    }
+    let w = (&k << n) - 1u8;
+    if let Some(result) = trial_division(&w) {
+        return Some(result.is_probable_prime());
+    }
+    Some(riesel_test(&w, &k, n).is_probable_prime())
+}
+
+/// Detect `w = k * 2^n - 1` with an odd `k < 2^n`
+///
+/// ## Returns
+///
+/// - `Some((k, n))` if `w` has the form
+/// - `None` otherwise
+pub(crate) fn riesel_form(w: &BigUint) -> Option<(BigUint, u64)> {
+    let w_plus_1 = w + 1u8;
+    let n = w_plus_1.trailing_zeros()?;
+    let k = w_plus_1 >> n;
+    (k.bits() <= n).then_some((k, n))
+}
+
+/// Run the Lucas-Lehmer-Riesel test on `w = k * 2^n - 1`, after trial division
+///
+/// ## Returns
+///
+/// - `Primality::ProvenPrime` if `w` is prime
+/// - `Primality::Composite` with a Miller-Rabin witness, if the sequence does not reach zero
+/// - `Primality::CompositeWithFactor` if a factor of `w` is found in the seed search
+pub(crate) fn riesel_test(w: &BigUint, k: &BigUint, n: u64) -> Primality {
+    let modulus = SpecialModulus::minus_one(k, n);
+    let p = match find_seed(w) {
+        Ok(p) => p,
+        Err(factor) => return Primality::CompositeWithFactor(factor),
+    };
+
+    let mut u = lucas_v(&modulus, p, k);
+    for _ in 2..n {
+        u = modulus.sub_small(modulus.mul(&u, &u), 2);
+    }
+    if u.is_zero() {
+        return Primality::ProvenPrime;
+    }
+    // the seed is not a witness in the sense of `Primality::Composite`, so one is searched
+    // among the small bases, of which at least three quarters are witnesses
+    let round = round_tester(w);
+    let witness = (2u32..)
+        .map(BigUint::from)
+        .find(|b| !round(b))
+        .expect("a composite number has a witness");
+    Primality::Composite { witness }
+}
+
+/// Search `P` with `((P - 2)/w) = 1` and `((P + 2)/w) = -1` (Rödseth)
+///
+/// ## Returns
+///
+/// - `Ok(P)` if the seed is found
+/// - `Err(factor)` if `P - 2` or `P + 2` shares a factor with `w`
+fn find_seed(w: &BigUint) -> Result<u64, BigUint> {
+    // `w` is not a perfect square since `w = 3 (mod 4)`, so the search terminates
+    for p in 3u64.. {
+        for m in [p - 2, p + 2] {
+            if jacobi_bigint(&BigInt::from(m), w) == 0 {
+                return Err(BigUint::from(m).gcd(w));
+            }
+        }
+        if jacobi_bigint(&BigInt::from(p - 2), w) == 1
+            && jacobi_bigint(&BigInt::from(p + 2), w) == -1
+        {
+            return Ok(p);
+        }
+    }
+    unreachable!("the seed search covers every residue")
+}
+
+/// Calculate the Lucas sequence `V_k(P, 1) mod w`
+///
+/// Uses `V_2m = V_m^2 - 2` and `V_(2m+1) = V_m V_(m+1) - P`.
+fn lucas_v(modulus: &SpecialModulus, p: u64, k: &BigUint) -> BigUint {
+    // (V_m, V_(m+1)) starting with m = 0
+    let mut v = BigUint::from(2u8);
+    let mut v_next = modulus.reduce(BigUint::from(p));
+    for i in (0..k.bits()).rev() {
+        let cross = modulus.sub_small(modulus.mul(&v, &v_next), p);
+        if k.bit(i) {
+            v = cross;
+            v_next = modulus.sub_small(modulus.mul(&v_next, &v_next), 2);
+        } else {
+            v_next = cross;
+            v = modulus.sub_small(modulus.mul(&v, &v), 2);
+        }
+    }
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::is_bpsw_prime;
+    use num_traits::One;
+
+    #[test]
+    fn is_riesel_prime_with_known_exponents() {
+        // exponents 2 <= n < 700 for which 3 * 2^n - 1 is prime
+        let exponents = [
+            2, 3, 4, 6, 7, 11, 18, 34, 38, 43, 55, 64, 76, 94, 103, 143, 206, 216, 306, 324, 391,
+            458, 470,
+        ];
+        let three = BigUint::from(3u8);
+        for n in 2..700 {
+            assert_eq!(
+                is_riesel_prime(&three, n),
+                Some(exponents.contains(&n)),
+                "n = {n}"
+            );
+        }
+    }
+
+    #[test]
+    fn is_riesel_prime_matches_bpsw() {
+        for k in (1u32..200).step_by(2) {
+            for n in [8, 13, 32, 64, 100] {
+                let w = (BigUint::from(k) << n) - 1u8;
+                assert_eq!(
+                    is_riesel_prime(&BigUint::from(k), n),
+                    Some(is_bpsw_prime(&w)),
+                    "k = {k}, n = {n}"
+                );
+            }
+        }
+        assert_eq!(is_riesel_prime(&BigUint::zero(), 10), None);
+        assert_eq!(is_riesel_prime(&BigUint::from(1025u32), 10), None);
+    }
+
+    #[test]
+    fn riesel_test_returns_miller_rabin_witness() {
+        let three = BigUint::from(3u8);
+        let mut composites = 0;
+        for n in 100..160 {
+            let w = (&three << n) - 1u8;
+            if trial_division(&w).is_some() {
+                continue;
+            }
+            match riesel_test(&w, &three, n) {
+                Primality::Composite { witness } => {
+                    assert!(!crate::miller_rabin_with_bases(&w, &[witness]), "n = {n}");
+                    composites += 1;
+                }
+                result => assert_eq!(result, Primality::ProvenPrime, "n = {n}"),
+            }
+        }
+        assert!(composites > 0);
+    }
+
+    #[test]
+    fn riesel_form_detection() {
+        let w = (BigUint::from(3u8) << 206) - 1u8;
+        assert_eq!(riesel_form(&w), Some((BigUint::from(3u8), 206)));
+        let w = (BigUint::one() << 127) - 1u8;
+        assert_eq!(riesel_form(&w), Some((BigUint::one(), 127)));
+        let w = (BigUint::from(1025u32) << 10) - 1u8;
+        assert_eq!(riesel_form(&w), None);
+    }
+}
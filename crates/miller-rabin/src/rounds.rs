@@ -0,0 +1,284 @@
+//! Selection of the number of Miller-Rabin rounds
+//!
+//! ## References
+//!
+//! - <https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.186-5.pdf>
+//!   - Appendix B.3, Table B.1 Minimum number of rounds of M-R testing
+//!     when generating primes for use in RSA Digital Signatures
+//!   - Appendix C.1 Computation of the probability that a candidate passes M-R testing
+//! - I. Damgård, P. Landrock and C. Pomerance,
+//!   "Average case error estimates for the strong probable prime test", Math. Comp. 61 (1993)
+
+use crate::lucas::{is_strong_lucas_probable_prime, selfridge_params};
+use crate::{check_primality_with_rng, miller_rabin_with_rng, trial_division};
+use num_bigint::BigUint;
+use num_traits::Float;
+
+/// Error probability `2^-100` required from the Miller-Rabin rounds followed by a Lucas test
+/// (FIPS 186-5 Appendix B.3)
+const LUCAS_MR_ERROR_BITS: u32 = 100;
+
+/// Calculate the minimum number of Miller-Rabin rounds for a random odd candidate
+///
+/// The result is the smallest `t` for which the probability that a random odd `bits`-bit
+/// integer passing `t` rounds is composite does not exceed `2^-error_bits`.
+/// It reproduces the "M-R Tests Only" values of FIPS 186-5 Table B.1,
+/// e.g. 5 rounds for 1024-bit primes with `2^-112`, 4 rounds for 1536-bit primes with `2^-128`.
+///
+/// ## Notes
+///
+/// The bound holds for randomly chosen candidates only.
+/// The result never exceeds `ceil(error_bits / 2)`, which is sufficient for any candidate.
+///
+/// ## Params
+///
+/// - `bits`: bit length of the candidate
+/// - `error_bits`: the target error probability is `2^-error_bits`
+///
+/// ## Returns
+///
+/// - the minimum number of rounds
+///
+/// ## Example
+///
+/// ```rust
+/// use yoshi389111_miller_rabin::min_rounds;
+///
+/// assert_eq!(min_rounds(1024, 112), 5);
+/// assert_eq!(min_rounds(1536, 128), 4);
+/// assert_eq!(min_rounds(141, 112), 38);
+/// ```
+pub fn min_rounds(bits: u64, error_bits: u32) -> usize {
+    let worst_case = error_bits.div_ceil(2) as usize;
+    (1..worst_case)
+        .find(|&t| log2_error_probability(bits, t) <= -f64::from(error_bits))
+        .unwrap_or(worst_case)
+        .max(1)
+}
+
+/// Calculate the minimum number of Miller-Rabin rounds when the rounds are followed by a strong Lucas test
+///
+/// As in the "M-R Tests + Lucas" column of FIPS 186-5 Table B.1, the Miller-Rabin rounds
+/// only need to bring the error probability down to `2^-100`, whatever the target:
+/// the result is [`min_rounds`] for `2^-min(error_bits, 100)`,
+/// e.g. 4 rounds for 1024-bit primes and 2 rounds for 2048-bit primes.
+///
+/// ## Params
+///
+/// - `bits`: bit length of the candidate
+/// - `error_bits`: the target error probability is `2^-error_bits`
+///
+/// ## Returns
+///
+/// - the minimum number of rounds
+///
+/// ## Example
+///
+/// ```rust
+/// use yoshi389111_miller_rabin::min_rounds_with_lucas;
+///
+/// assert_eq!(min_rounds_with_lucas(1024, 112), 4);
+/// assert_eq!(min_rounds_with_lucas(2048, 144), 2);
+/// ```
+pub fn min_rounds_with_lucas(bits: u64, error_bits: u32) -> usize {
+    min_rounds(bits, error_bits.min(LUCAS_MR_ERROR_BITS))
+}
+
+/// Check if a BigUint is probably prime, choosing the number of rounds from the target error probability
+///
+/// ## Params
+///
+/// - `w`: the number to be tested for primality
+/// - `error_bits`: the target error probability is `2^-error_bits`
+/// - `rng`: random number generator
+///
+/// ## Returns
+///
+/// - `true` if `w` is probably prime
+/// - `false` if `w` is definitely composite
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use rand::rngs::OsRng;
+/// use yoshi389111_miller_rabin::is_probable_prime_for_error_with_rng;
+///
+/// let w = BigUint::from(389_111_u64);
+/// let mut rng = OsRng;
+/// assert!(is_probable_prime_for_error_with_rng(&w, 128, &mut rng));
+/// ```
+pub fn is_probable_prime_for_error_with_rng<R: rand::Rng + ?Sized>(
+    w: &BigUint,
+    error_bits: u32,
+    rng: &mut R,
+) -> bool {
+    let iter = min_rounds(w.bits(), error_bits);
+    check_primality_with_rng(w, iter, rng).is_probable_prime()
+}
+
+/// Check if a BigUint is probably prime with OS random number generator, choosing the number of rounds from the target error probability
+///
+/// ## Params
+///
+/// - `w`: the number to be tested for primality
+/// - `error_bits`: the target error probability is `2^-error_bits`
+///
+/// ## Returns
+///
+/// - `true` if `w` is probably prime
+/// - `false` if `w` is definitely composite
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use yoshi389111_miller_rabin::is_probable_prime_for_error;
+///
+/// let w = BigUint::from(389_111_u64);
+/// assert!(is_probable_prime_for_error(&w, 128));
+/// ```
+#[cfg(feature = "std")]
+pub fn is_probable_prime_for_error(w: &BigUint, error_bits: u32) -> bool {
+    is_probable_prime_for_error_with_rng(w, error_bits, &mut rand::rngs::OsRng)
+}
+
+/// Check if a BigUint is probably prime using Miller-Rabin rounds followed by a strong Lucas test,
+/// choosing the number of rounds from the target error probability
+///
+/// ## Params
+///
+/// - `w`: the number to be tested for primality
+/// - `error_bits`: the target error probability is `2^-error_bits`
+/// - `rng`: random number generator
+///
+/// ## Returns
+///
+/// - `true` if `w` is probably prime
+/// - `false` if `w` is definitely composite
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use rand::rngs::OsRng;
+/// use yoshi389111_miller_rabin::is_probable_prime_with_lucas_for_error_with_rng;
+///
+/// let w = BigUint::from(389_111_u64);
+/// let mut rng = OsRng;
+/// assert!(is_probable_prime_with_lucas_for_error_with_rng(&w, 128, &mut rng));
+/// ```
+pub fn is_probable_prime_with_lucas_for_error_with_rng<R: rand::Rng + ?Sized>(
+    w: &BigUint,
+    error_bits: u32,
+    rng: &mut R,
+) -> bool {
+    if let Some(result) = trial_division(w) {
+        return result.is_probable_prime();
+    }
+    let iter = min_rounds_with_lucas(w.bits(), error_bits);
+    if !miller_rabin_with_rng(w, iter, rng).is_probable_prime() {
+        return false;
+    }
+    match selfridge_params(w) {
+        Some(params) => is_strong_lucas_probable_prime(w, &params),
+        None => false,
+    }
+}
+
+/// Check if a BigUint is probably prime using Miller-Rabin rounds followed by a strong Lucas test
+/// with OS random number generator, choosing the number of rounds from the target error probability
+///
+/// ## Params
+///
+/// - `w`: the number to be tested for primality
+/// - `error_bits`: the target error probability is `2^-error_bits`
+///
+/// ## Returns
+///
+/// - `true` if `w` is probably prime
+/// - `false` if `w` is definitely composite
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use yoshi389111_miller_rabin::is_probable_prime_with_lucas_for_error;
+///
+/// let w = BigUint::from(389_111_u64);
+/// assert!(is_probable_prime_with_lucas_for_error(&w, 128));
+/// ```
+#[cfg(feature = "std")]
+pub fn is_probable_prime_with_lucas_for_error(w: &BigUint, error_bits: u32) -> bool {
+    is_probable_prime_with_lucas_for_error_with_rng(w, error_bits, &mut rand::rngs::OsRng)
+}
+
+/// Calculate `log2(p_{k,t})`, an upper bound of the probability that
+/// a random odd `k`-bit integer passing `t` rounds is composite
+///
+/// `p_{k,t} <= 2.00743 * ln(2) * k * 2^-k * (2^(k-2-M*t) + 8 * (pi^2 - 6) / 3 * 2^(k-2) * S(M))`,
+/// `S(M) = sum_{m=3}^{M} sum_{j=2}^{m} 2^(m - (m-1)*t - j - (k-1)/j)`,
+/// minimized over `3 <= M <= 2 * sqrt(k - 1) - 1`.
+/// The factor `2^-k * 2^k` is canceled in advance to avoid overflow.
+fn log2_error_probability(k: u64, t: usize) -> f64 {
+    let kf = k as f64;
+    let tf = t as f64;
+    let max_m = (2.0 * Float::sqrt(kf - 1.0) - 1.0) as u64;
+    if max_m < 3 {
+        // the bound is not applicable to such small numbers
+        return 0.0;
+    }
+
+    let coefficient = 2.00743 * core::f64::consts::LN_2 * kf;
+    let c = 8.0 * (core::f64::consts::PI * core::f64::consts::PI - 6.0) / 3.0;
+    let mut sum = 0.0;
+    let mut best = f64::INFINITY;
+    for m in 3..=max_m {
+        let mf = m as f64;
+        sum += (2..=m)
+            .map(|j| {
+                let jf = j as f64;
+                Float::exp2(mf - (mf - 1.0) * tf - jf - (kf - 1.0) / jf)
+            })
+            .sum::<f64>();
+        let p = coefficient * (Float::exp2(-2.0 - mf * tf) + c * 0.25 * sum);
+        best = best.min(p);
+    }
+    Float::log2(best)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn min_rounds_matches_fips_186_5_table_b1() {
+        // p and q
+        assert_eq!(min_rounds(1024, 112), 5);
+        assert_eq!(min_rounds(1536, 128), 4);
+        assert_eq!(min_rounds(2048, 144), 4);
+        // auxiliary primes p1, p2, q1 and q2
+        assert_eq!(min_rounds(141, 112), 38);
+        assert_eq!(min_rounds(171, 128), 41);
+        assert_eq!(min_rounds(201, 144), 44);
+    }
+
+    #[test]
+    fn min_rounds_with_lucas_matches_fips_186_5_table_b1() {
+        // p and q
+        assert_eq!(min_rounds_with_lucas(1024, 112), 4);
+        assert_eq!(min_rounds_with_lucas(1536, 128), 3);
+        assert_eq!(min_rounds_with_lucas(2048, 144), 2);
+        // auxiliary primes p1, p2, q1 and q2
+        assert_eq!(min_rounds_with_lucas(141, 112), 32);
+        assert_eq!(min_rounds_with_lucas(171, 128), 27);
+        assert_eq!(min_rounds_with_lucas(201, 144), 22);
+    }
+
+    #[test]
+    fn min_rounds_is_bounded_by_worst_case() {
+        assert_eq!(min_rounds(8, 100), 50);
+        assert_eq!(min_rounds(64, 1), 1);
+        assert_eq!(min_rounds_with_lucas(2048, 1), 1);
+    }
+}
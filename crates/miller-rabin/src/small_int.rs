@@ -0,0 +1,533 @@
+//! Deterministic primality tests for machine-word integers
+//!
+//! These functions work on native integers only, so no heap allocation
+//! and no random number generator are required.
+//!
+//! ## References
+//!
+//! - G. Jaeschke, "On strong pseudoprimes to several bases", Math. Comp. 61 (1993)
+//! - J. Sorenson and J. Webster, "Strong pseudoprimes to twelve prime bases", Math. Comp. 86 (2017)
+//! - P. L. Montgomery, "Modular multiplication without trial division", Math. Comp. 44 (1985)
+
+/// Small primes used for trial division and as Miller-Rabin bases
+const SMALL_PRIMES: [u8; 13] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41];
+
+/// Bases that make Miller-Rabin deterministic for `n < 4_759_123_141` (Jaeschke)
+const BASES_U32: [u64; 3] = [2, 7, 61];
+
+/// Upper bound for which the first 13 primes are deterministic bases (Sorenson-Webster)
+const BASES_13_PRIMES_LIMIT: u128 = 3_317_044_064_679_887_385_961_981;
+
+/// Check if a `u32` number is prime
+///
+/// ## Params
+///
+/// - `n`: the number to be tested for primality
+///
+/// ## Returns
+///
+/// - `true` if `n` is prime
+/// - `false` if `n` is composite, zero or one
+///
+/// ## Example
+///
+/// ```rust
+/// use yoshi389111_miller_rabin::is_prime_u32;
+///
+/// assert!(is_prime_u32(389_111));
+/// assert!(!is_prime_u32(389_113));
+/// ```
+pub fn is_prime_u32(n: u32) -> bool {
+    if let Some(result) = trial_division(u128::from(n)) {
+        return result;
+    }
+
+    let n = u64::from(n);
+    let n_minus_1 = n - 1;
+    let s = n_minus_1.trailing_zeros();
+    let d = n_minus_1 >> s;
+    BASES_U32.iter().all(|&base| {
+        // `n < 2^32`, so the products always fit in `u64`
+        let mut z = pow_mod_u64(base, d, n);
+        if z == 1 || z == n_minus_1 {
+            return true;
+        }
+        for _ in 1..s {
+            z = z * z % n;
+            if z == n_minus_1 {
+                return true;
+            }
+        }
+        false
+    })
+}
+
+/// Check if a `u64` number is prime
+///
+/// ## Params
+///
+/// - `n`: the number to be tested for primality
+///
+/// ## Returns
+///
+/// - `true` if `n` is prime
+/// - `false` if `n` is composite, zero or one
+///
+/// ## Example
+///
+/// ```rust
+/// use yoshi389111_miller_rabin::is_prime_u64;
+///
+/// assert!(is_prime_u64(18_446_744_073_709_551_557));
+/// assert!(!is_prime_u64(389_111 * 389_111));
+/// ```
+pub fn is_prime_u64(n: u64) -> bool {
+    if let Ok(n) = u32::try_from(n) {
+        return is_prime_u32(n);
+    }
+    if let Some(result) = trial_division(u128::from(n)) {
+        return result;
+    }
+
+    // the first 12 primes are deterministic bases for all `n < 2^64`
+    let mont = Montgomery64::new(n);
+    SMALL_PRIMES[..12]
+        .iter()
+        .all(|&base| mont.is_strong_probable_prime(u64::from(base)))
+}
+
+/// Check if a `u128` number is prime
+///
+/// ## Notes
+///
+/// For `n < 3_317_044_064_679_887_385_961_981` the result is proven by Miller-Rabin
+/// with the first 13 primes as bases.
+/// Larger numbers are checked with the Baillie-PSW test,
+/// for which no counterexample is known.
+///
+/// ## Params
+///
+/// - `n`: the number to be tested for primality
+///
+/// ## Returns
+///
+/// - `true` if `n` is prime, or a Baillie-PSW probable prime for `n >= 3_317_044_064_679_887_385_961_981`
+/// - `false` if `n` is composite, zero or one
+///
+/// ## Example
+///
+/// ```rust
+/// use yoshi389111_miller_rabin::is_prime_u128;
+///
+/// assert!(is_prime_u128((1 << 127) - 1));
+/// assert!(!is_prime_u128((1 << 127) + 1));
+/// ```
+pub fn is_prime_u128(n: u128) -> bool {
+    if let Ok(n) = u64::try_from(n) {
+        return is_prime_u64(n);
+    }
+    if let Some(result) = trial_division(n) {
+        return result;
+    }
+
+    let mont = Montgomery128::new(n);
+    if n < BASES_13_PRIMES_LIMIT {
+        SMALL_PRIMES
+            .iter()
+            .all(|&base| mont.is_strong_probable_prime(u128::from(base)))
+    } else {
+        mont.is_strong_probable_prime(2) && mont.is_strong_lucas_probable_prime()
+    }
+}
+
+/// Trial division by `SMALL_PRIMES`
+///
+/// Returns `Some(result)` if the primality of `n` is decided, otherwise `None`.
+fn trial_division(n: u128) -> Option<bool> {
+    if n < 2 {
+        return Some(false);
+    }
+    for &p in SMALL_PRIMES.iter() {
+        let p = u128::from(p);
+        if n == p {
+            return Some(true);
+        }
+        if n.is_multiple_of(p) {
+            return Some(false);
+        }
+    }
+    // no prime factor less than 43 and `n < 43^2`
+    if n < 43 * 43 { Some(true) } else { None }
+}
+
+/// Calculate `base^exp mod n` for `n < 2^32`
+fn pow_mod_u64(base: u64, mut exp: u64, n: u64) -> u64 {
+    let mut base = base % n;
+    let mut result = 1;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result * base % n;
+        }
+        base = base * base % n;
+        exp >>= 1;
+    }
+    result
+}
+
+/// Montgomery arithmetic modulo an odd `u64` number
+struct Montgomery64 {
+    /// the modulus
+    n: u64,
+    /// `-n^-1 mod 2^64`
+    n_neg_inv: u64,
+    /// `2^128 mod n`
+    r2: u64,
+}
+
+impl Montgomery64 {
+    /// Create a new context for the odd modulus `n`
+    fn new(n: u64) -> Self {
+        debug_assert!(n % 2 == 1);
+        // Newton's iteration: each step doubles the number of correct low bits
+        let mut inv = n;
+        for _ in 0..5 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(n.wrapping_mul(inv)));
+        }
+        let r1 = (1u128 << 64) % u128::from(n);
+        let r2 = (r1 * r1 % u128::from(n)) as u64;
+        Self {
+            n,
+            n_neg_inv: inv.wrapping_neg(),
+            r2,
+        }
+    }
+
+    /// Montgomery reduction: `t * 2^-64 mod n` for `t < n * 2^64`
+    fn redc(&self, t: u128) -> u64 {
+        let m = (t as u64).wrapping_mul(self.n_neg_inv);
+        let (sum, carry) = t.overflowing_add(u128::from(m) * u128::from(self.n));
+        let u = (sum >> 64) as u64;
+        if carry || u >= self.n {
+            u.wrapping_sub(self.n)
+        } else {
+            u
+        }
+    }
+
+    fn mul(&self, a: u64, b: u64) -> u64 {
+        self.redc(u128::from(a) * u128::from(b))
+    }
+
+    fn to_mont(&self, a: u64) -> u64 {
+        self.mul(a % self.n, self.r2)
+    }
+
+    fn pow(&self, base: u64, mut exp: u64) -> u64 {
+        let mut base = base;
+        let mut result = self.to_mont(1);
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = self.mul(result, base);
+            }
+            base = self.mul(base, base);
+            exp >>= 1;
+        }
+        result
+    }
+
+    /// Strong probable prime test to the given base
+    fn is_strong_probable_prime(&self, base: u64) -> bool {
+        let n_minus_1 = self.n - 1;
+        let s = n_minus_1.trailing_zeros();
+        let d = n_minus_1 >> s;
+        let one = self.to_mont(1);
+        let minus_one = self.n - one;
+
+        let mut z = self.pow(self.to_mont(base), d);
+        if z == one || z == minus_one {
+            return true;
+        }
+        for _ in 1..s {
+            z = self.mul(z, z);
+            if z == minus_one {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Calculate the full 256-bit product of two `u128` numbers as `(high, low)`
+fn mul_wide_u128(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & MASK);
+    let (b_hi, b_lo) = (b >> 64, b & MASK);
+
+    let lo_lo = a_lo * b_lo;
+    let hi_lo = a_hi * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_hi = a_hi * b_hi;
+
+    // none of these additions can overflow
+    let cross = (lo_lo >> 64) + (hi_lo & MASK) + lo_hi;
+    let high = hi_hi + (hi_lo >> 64) + (cross >> 64);
+    let low = (cross << 64) | (lo_lo & MASK);
+    (high, low)
+}
+
+/// Montgomery arithmetic modulo an odd `u128` number
+struct Montgomery128 {
+    /// the modulus
+    n: u128,
+    /// `-n^-1 mod 2^128`
+    n_neg_inv: u128,
+    /// `2^256 mod n`
+    r2: u128,
+}
+
+impl Montgomery128 {
+    /// Create a new context for the odd modulus `n`
+    fn new(n: u128) -> Self {
+        debug_assert!(n % 2 == 1);
+        // Newton's iteration: each step doubles the number of correct low bits
+        let mut inv = n;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2u128.wrapping_sub(n.wrapping_mul(inv)));
+        }
+        let mut r2 = n.wrapping_neg() % n; // 2^128 mod n
+        for _ in 0..128 {
+            r2 = add_mod_u128(r2, r2, n);
+        }
+        Self {
+            n,
+            n_neg_inv: inv.wrapping_neg(),
+            r2,
+        }
+    }
+
+    /// Montgomery reduction: `(high * 2^128 + low) * 2^-128 mod n`
+    fn redc(&self, high: u128, low: u128) -> u128 {
+        let m = low.wrapping_mul(self.n_neg_inv);
+        let (mn_high, mn_low) = mul_wide_u128(m, self.n);
+        // the low half of the sum is always zero, only the carry is needed
+        let (_, carry_low) = low.overflowing_add(mn_low);
+        let (sum, carry1) = high.overflowing_add(mn_high);
+        let (sum, carry2) = sum.overflowing_add(u128::from(carry_low));
+        if carry1 || carry2 || sum >= self.n {
+            sum.wrapping_sub(self.n)
+        } else {
+            sum
+        }
+    }
+
+    fn mul(&self, a: u128, b: u128) -> u128 {
+        let (high, low) = mul_wide_u128(a, b);
+        self.redc(high, low)
+    }
+
+    fn to_mont(&self, a: u128) -> u128 {
+        self.mul(a % self.n, self.r2)
+    }
+
+    fn add(&self, a: u128, b: u128) -> u128 {
+        add_mod_u128(a, b, self.n)
+    }
+
+    fn sub(&self, a: u128, b: u128) -> u128 {
+        if a >= b {
+            a - b
+        } else {
+            a.wrapping_sub(b).wrapping_add(self.n)
+        }
+    }
+
+    /// Calculate `a / 2 mod n`
+    fn half(&self, a: u128) -> u128 {
+        if a & 1 == 0 {
+            a >> 1
+        } else {
+            // `(a + n) / 2` without overflow, both `a` and `n` are odd
+            (a >> 1) + (self.n >> 1) + 1
+        }
+    }
+
+    fn pow(&self, base: u128, mut exp: u128) -> u128 {
+        let mut base = base;
+        let mut result = self.to_mont(1);
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = self.mul(result, base);
+            }
+            base = self.mul(base, base);
+            exp >>= 1;
+        }
+        result
+    }
+
+    /// Strong probable prime test to the given base
+    fn is_strong_probable_prime(&self, base: u128) -> bool {
+        let n_minus_1 = self.n - 1;
+        let s = n_minus_1.trailing_zeros();
+        let d = n_minus_1 >> s;
+        let one = self.to_mont(1);
+        let minus_one = self.n - one;
+
+        let mut z = self.pow(self.to_mont(base), d);
+        if z == one || z == minus_one {
+            return true;
+        }
+        for _ in 1..s {
+            z = self.mul(z, z);
+            if z == minus_one {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Strong Lucas probable prime test with Selfridge's parameters (`P = 1`, `Q = (1 - D) / 4`)
+    fn is_strong_lucas_probable_prime(&self) -> bool {
+        let n = self.n;
+        if is_square_u128(n) {
+            return false;
+        }
+
+        // Selfridge's method A: the first D in 5, -7, 9, -11, ... with (D/n) = -1
+        let mut d: i128 = 5;
+        loop {
+            match jacobi_u128(d, n) {
+                -1 => break,
+                0 if d.unsigned_abs() != n => return false,
+                _ => {}
+            }
+            d = if d > 0 { -d - 2 } else { -d + 2 };
+        }
+        let q = (1 - d) / 4;
+
+        let to_mont_signed = |x: i128| {
+            let m = self.to_mont(x.unsigned_abs());
+            if x < 0 { self.sub(0, m) } else { m }
+        };
+        let d_m = to_mont_signed(d);
+        let q_m = to_mont_signed(q);
+        let one = self.to_mont(1);
+
+        // `n` is odd and not `u128::MAX` (divisible by 3), so `n + 1` does not overflow
+        let n_plus_1 = n + 1;
+        let s = n_plus_1.trailing_zeros();
+        let k = n_plus_1 >> s;
+
+        // binary ladder from the most significant bit of `k` (P = 1)
+        let mut u = one;
+        let mut v = one;
+        let mut qk = q_m;
+        for i in (0..(127 - k.leading_zeros())).rev() {
+            u = self.mul(u, v);
+            v = self.sub(self.mul(v, v), self.add(qk, qk));
+            qk = self.mul(qk, qk);
+            if (k >> i) & 1 == 1 {
+                let u_next = self.half(self.add(u, v));
+                v = self.half(self.add(self.mul(d_m, u), v));
+                u = u_next;
+                qk = self.mul(qk, q_m);
+            }
+        }
+
+        if u == 0 || v == 0 {
+            return true;
+        }
+        for _ in 1..s {
+            v = self.sub(self.mul(v, v), self.add(qk, qk));
+            if v == 0 {
+                return true;
+            }
+            qk = self.mul(qk, qk);
+        }
+        false
+    }
+}
+
+/// Calculate `(a + b) mod n` for `a, b < n`
+pub(crate) fn add_mod_u128(a: u128, b: u128, n: u128) -> u128 {
+    let (sum, carry) = a.overflowing_add(b);
+    if carry || sum >= n {
+        sum.wrapping_sub(n)
+    } else {
+        sum
+    }
+}
+
+/// Check if `n` is a perfect square
+fn is_square_u128(n: u128) -> bool {
+    use num_integer::Roots;
+    let root = n.sqrt();
+    root * root == n
+}
+
+/// Calculate the Jacobi symbol `(a/n)` for an odd positive `n`
+fn jacobi_u128(a: i128, n: u128) -> i8 {
+    let mut a = if a < 0 {
+        // `(a mod n)` computed without overflow
+        let r = a.unsigned_abs() % n;
+        if r == 0 { 0 } else { n - r }
+    } else {
+        a.unsigned_abs() % n
+    };
+    let mut n = n;
+    let mut result = 1;
+    while a != 0 {
+        let tz = a.trailing_zeros();
+        a >>= tz;
+        if tz % 2 == 1 && (n % 8 == 3 || n % 8 == 5) {
+            result = -result;
+        }
+        if a % 4 == 3 && n % 4 == 3 {
+            result = -result;
+        }
+        (a, n) = (n % a, a);
+    }
+    if n == 1 { result } else { 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn is_prime_u32_matches_sieve() {
+        let primes: Vec<u32> = yoshi389111_prime_iter::new()
+            .take_while(|&p| p < 100_000)
+            .collect();
+        let result: Vec<u32> = (0..100_000).filter(|&n| is_prime_u32(n)).collect();
+        assert_eq!(result, primes);
+    }
+
+    #[test]
+    fn is_prime_u32_rejects_strong_pseudoprimes() {
+        // strong pseudoprime to bases 2, 3, 5 and 7
+        assert!(!is_prime_u32(3_215_031_751));
+        assert!(is_prime_u32(4_294_967_291));
+    }
+
+    #[test]
+    fn is_prime_u64_with_large_values() {
+        assert!(is_prime_u64(18_446_744_073_709_551_557));
+        assert!(!is_prime_u64(18_446_744_073_709_551_559));
+        // strong pseudoprime to all prime bases up to 23
+        assert!(!is_prime_u64(3_825_123_056_546_413_051));
+        assert!(is_prime_u64((1 << 61) - 1));
+    }
+
+    #[test]
+    fn is_prime_u128_with_large_values() {
+        assert!(is_prime_u128((1 << 89) - 1));
+        assert!(is_prime_u128((1 << 127) - 1));
+        assert!(is_prime_u128(u128::MAX - 158)); // largest prime below 2^128
+        assert!(!is_prime_u128(((1 << 61) - 1) * ((1 << 61) - 1)));
+        assert!(!is_prime_u128(((1 << 61) - 1) * ((1 << 67) + 3)));
+        assert!(!is_prime_u128(
+            18_446_744_073_709_551_557 * 18_446_744_073_709_551_557
+        ));
+    }
+}
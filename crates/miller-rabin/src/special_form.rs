@@ -0,0 +1,125 @@
+//! Fast reduction modulo numbers of the form `k * 2^n + 1` and `k * 2^n - 1`
+//!
+//! ## References
+//!
+//! - R. Crandall and C. Pomerance, "Prime Numbers: A Computational Perspective", Section 9.2.3
+
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::{One, Zero};
+
+/// Modulus `w = k * 2^n + 1` or `w = k * 2^n - 1` with the reduction by shifts
+pub(crate) struct SpecialModulus<'a> {
+    /// the multiplier
+    k: &'a BigUint,
+    /// the exponent of two
+    n: u64,
+    /// `true` for `k * 2^n + 1`, `false` for `k * 2^n - 1`
+    plus_one: bool,
+    /// `2^n - 1`
+    mask: BigUint,
+    /// the modulus
+    w: BigUint,
+}
+
+impl<'a> SpecialModulus<'a> {
+    /// Create the modulus `k * 2^n + 1`
+    pub(crate) fn plus_one(k: &'a BigUint, n: u64) -> Self {
+        Self::new(k, n, true)
+    }
+
+    /// Create the modulus `k * 2^n - 1`
+    pub(crate) fn minus_one(k: &'a BigUint, n: u64) -> Self {
+        Self::new(k, n, false)
+    }
+
+    fn new(k: &'a BigUint, n: u64, plus_one: bool) -> Self {
+        let power = k << n;
+        SpecialModulus {
+            k,
+            n,
+            plus_one,
+            mask: (BigUint::one() << n) - 1u8,
+            w: if plus_one { power + 1u8 } else { power - 1u8 },
+        }
+    }
+
+    /// Reduce `x` modulo `w`, using `k * 2^n = -1 (mod w)` or `k * 2^n = 1 (mod w)`
+    ///
+    /// With `x = h * 2^n + l` and `h = q * k + r`, `x = r * 2^n + l -/+ q (mod w)`,
+    /// where `r * 2^n + l <= w`.
+    pub(crate) fn reduce(&self, x: BigUint) -> BigUint {
+        if x < self.w {
+            return x;
+        }
+        let low = &x & &self.mask;
+        let high = x >> self.n;
+        let (q, r) = if self.k.is_one() {
+            (high, BigUint::zero())
+        } else {
+            high.div_rem(self.k)
+        };
+        let t = (r << self.n) + low;
+        let q = self.reduce(q);
+        if self.plus_one {
+            if t >= q { t - q } else { t + &self.w - q }
+        } else {
+            let sum = t + q;
+            if sum >= self.w { sum - &self.w } else { sum }
+        }
+    }
+
+    /// Calculate `a * b mod w`
+    pub(crate) fn mul(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        self.reduce(a * b)
+    }
+
+    /// Calculate `a - c mod w` for `a < w` and a small `c < w`
+    pub(crate) fn sub_small(&self, a: BigUint, c: u64) -> BigUint {
+        if a >= BigUint::from(c) {
+            a - c
+        } else {
+            a + &self.w - c
+        }
+    }
+
+    /// Calculate `base^e mod w` for a small `base`
+    pub(crate) fn pow(&self, base: &BigUint, e: &BigUint) -> BigUint {
+        let mut result = BigUint::one();
+        for i in (0..e.bits()).rev() {
+            result = self.mul(&result, &result);
+            if e.bit(i) {
+                result = self.mul(&result, base);
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reduce_matches_remainder() {
+        for k in [1u32, 3, 5, 255] {
+            let k = BigUint::from(k);
+            for modulus in [
+                SpecialModulus::plus_one(&k, 70),
+                SpecialModulus::minus_one(&k, 70),
+            ] {
+                let w = modulus.w.clone();
+                for x in [
+                    BigUint::zero(),
+                    w.clone(),
+                    &w - 1u8,
+                    &w * &w - 1u8,
+                    (&w - 1u8) * (&w - 2u8),
+                    (BigUint::one() << 150) + 12345u32,
+                ] {
+                    assert_eq!(modulus.reduce(x.clone()), x % &w);
+                }
+            }
+        }
+    }
+}
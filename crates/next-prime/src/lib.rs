@@ -12,14 +12,27 @@
 //! assert_eq!(next_prime, BigUint::from(11_u32));
 //! ```
 //!
+//! The crate is `no_std` with `alloc` when the default `std` feature is disabled.
+//!
+//! There is no `gmp` feature here: the search only adds small numbers to the candidate,
+//! and almost all of the time is spent in `is_prime`. Passing a predicate built on
+//! `yoshi389111-miller-rabin` with its `gmp` feature enabled gives the GMP speedup to `find`.
+//!
 //! # References
 //! - [Wheel Factorization - Wikipedia](https://en.wikipedia.org/wiki/Wheel_factorization)
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 mod wheel_sieve;
 use crate::wheel_sieve::WheelSieve;
+use alloc::sync::Arc;
 use num_bigint::BigUint;
+#[cfg(feature = "std")]
 use once_cell::sync::Lazy;
-use std::sync::Arc;
+#[cfg(not(feature = "std"))]
+use spin::Lazy;
 
 /// The number of prime numbers used in the wheel sieve. (p4# = 2*3*5*7 = 210)
 const WHEEL_PRIME_COUNT: usize = 4;
@@ -1,8 +1,9 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
 use itertools::Itertools;
 use num_bigint::BigUint;
 use num_integer::Integer;
 use num_traits::ToPrimitive;
-use std::sync::Arc;
 
 /// Structure representing a wheel sieve for prime number generation
 pub(crate) struct WheelSieve {
@@ -69,8 +70,8 @@ impl Iterator for WheelSieveIter {
     fn next(&mut self) -> Option<Self::Item> {
         let diffs = &self.sieve.diffs;
         let next_value = &self.next_value + diffs[self.index];
-        // Note: Using std::mem::replace to avoid cloning the BigUint
-        let current_value = std::mem::replace(&mut self.next_value, next_value);
+        // Note: Using core::mem::replace to avoid cloning the BigUint
+        let current_value = core::mem::replace(&mut self.next_value, next_value);
         self.index = (self.index + 1) % diffs.len();
         Some(current_value)
     }
@@ -79,6 +80,7 @@ impl Iterator for WheelSieveIter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
 
     #[test]
     fn primorial_calculates_correctly() {
@@ -0,0 +1,657 @@
+//! Elliptic curve primality proving (Atkin-Morain ECPP)
+//!
+//! Each step of the certificate proves a number `n` prime by the Goldwasser-Kilian theorem:
+//! let `E` be an elliptic curve modulo `n` with `gcd(n, 6) = 1`, `m = k * q` where `q` is prime
+//! and `q > (n^(1/4) + 1)^2`, and `P` a point on `E` with `[k] P != O` and `[m] P = O`.
+//! Then `n` is prime. The primality of `q` is proven by the next step,
+//! until `q` fits in 64 bits, where the deterministic test for `u64` is a proof.
+//!
+//! The curves are built by complex multiplication: for a discriminant `D` with
+//! `4n = t^2 + |D| s^2`, the curve whose j-invariant is a root of the Hilbert class polynomial
+//! `H_D` modulo `n`, or its quadratic twist, has `n + 1 - t` or `n + 1 + t` points.
+//! The discriminants are tried in ascending order of the class number, and the class polynomial
+//! of each discriminant is computed once, then kept for the later steps and certificates.
+//!
+//! ## Text format
+//!
+//! The certificate is written in sections of `key=value` lines with hexadecimal numbers
+//! prefixed by `$`, borrowing the layout of Primo certificates.
+//! It is not readable by Primo, and making it so is impractical: Primo does not store a step
+//! as a curve and a point, but as parameters from which its verifier rebuilds them by its own
+//! conventions, and its files carry Primo-specific header fields. The steps would have to be
+//! built the way Primo builds them, and the result could not be checked without Primo itself.
+//! The format below stores the curve and the point as they are, so that any verifier can check
+//! a step without such conventions:
+//!
+//! ```text
+//! [ECPP - Primality Certificate]
+//! Format=1
+//!
+//! [Candidate]
+//! N=$...
+//!
+//! [1]
+//! N=$...
+//! A=$...
+//! B=$...
+//! M=$...
+//! Q=$...
+//! X=$...
+//! Y=$...
+//! ```
+//!
+//! Section `[i]` proves `N` of step `i` with the curve `y^2 = x^3 + A x + B`,
+//! the number of points `M`, the prime factor `Q` of `M` and the point `(X, Y)`.
+//! `Q` of each step is `N` of the next step, and `Q` of the last step fits in 64 bits.
+//!
+//! ## References
+//!
+//! - A. O. L. Atkin and F. Morain, "Elliptic curves and primality proving", Math. Comp. 61 (1993)
+//! - S. Goldwasser and J. Kilian, "Primality testing using elliptic curves", J. ACM 46 (1999)
+//! - H. Cohen, "A Course in Computational Algebraic Number Theory", Section 9.2
+
+use crate::ParseCertificateError;
+use crate::elliptic::{Curve, Point};
+use crate::hilbert::{Form, hilbert_class_polynomial, reduced_forms_up_to};
+use crate::modular::{SqrtContext, kronecker_small};
+use crate::polynomial::find_root;
+use num_bigint::{BigInt, BigUint};
+use num_integer::Integer;
+use num_traits::{One, ToPrimitive, Zero};
+use once_cell::sync::{Lazy, OnceCell};
+use std::fmt;
+use std::str::FromStr;
+use yoshi389111_miller_rabin::{is_bpsw_prime, is_prime_u64};
+
+/// Maximum absolute value of the discriminants
+const MAX_DISCRIMINANT: u64 = 1 << 18;
+
+/// Maximum class number of the discriminants
+const MAX_CLASS_NUMBER: usize = 48;
+
+/// Maximum prime removed from the number of points
+const MAX_SMALL_FACTOR: u32 = 1 << 20;
+
+/// Maximum number of points tried on each curve
+const MAX_POINTS: u32 = 100;
+
+/// Maximum number of factors `q` given up in favor of another one
+const MAX_BACKTRACKS: usize = 20;
+
+/// A fundamental discriminant `-d` with its reduced forms
+struct Discriminant {
+    /// the absolute value of the discriminant
+    d: u64,
+    /// the reduced forms, as many as the class number
+    forms: Vec<Form>,
+    /// the Hilbert class polynomial, computed on first use
+    poly: OnceCell<Option<Vec<BigInt>>>,
+}
+
+/// Fundamental discriminants, in ascending order of the class number
+///
+/// `-3` and `-4` are excluded, since their curves have more than two twists.
+static DISCRIMINANTS: Lazy<Vec<Discriminant>> = Lazy::new(|| {
+    let squarefree = squarefree_sieve(MAX_DISCRIMINANT);
+    let mut discriminants: Vec<_> = reduced_forms_up_to(MAX_DISCRIMINANT, |d, class_number| {
+        d > 4 && class_number <= MAX_CLASS_NUMBER && is_fundamental_discriminant(d, &squarefree)
+    })
+    .into_iter()
+    .map(|(d, forms)| Discriminant {
+        d,
+        forms,
+        poly: OnceCell::new(),
+    })
+    .collect();
+    discriminants.sort_by_key(|disc| (disc.forms.len(), disc.d));
+    discriminants
+});
+
+/// Product of the primes removed from the number of points
+static SMALL_PRIMORIAL: Lazy<BigUint> = Lazy::new(|| {
+    let mut factors: Vec<BigUint> = yoshi389111_prime_iter::new::<u32>()
+        .take_while(|p| *p < MAX_SMALL_FACTOR)
+        .map(BigUint::from)
+        .collect();
+    // multiply in pairs, so that the operands are of similar sizes
+    while factors.len() > 1 {
+        factors = factors
+            .chunks(2)
+            .map(|pair| pair.iter().product())
+            .collect();
+    }
+    factors.pop().unwrap_or_else(BigUint::one)
+});
+
+/// ECPP primality certificate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EcppCertificate {
+    /// the certified prime
+    pub n: BigUint,
+    /// the steps, from `n` down to a prime that fits in 64 bits
+    pub steps: Vec<EcppStep>,
+}
+
+/// A step of an ECPP certificate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EcppStep {
+    /// the number proven prime by this step
+    pub n: BigUint,
+    /// the coefficient `a` of the curve `y^2 = x^3 + a x + b`
+    pub a: BigUint,
+    /// the coefficient `b` of the curve `y^2 = x^3 + a x + b`
+    pub b: BigUint,
+    /// the number of points on the curve
+    pub m: BigUint,
+    /// the prime factor of `m`, proven prime by the next step
+    pub q: BigUint,
+    /// the x-coordinate of the point
+    pub x: BigUint,
+    /// the y-coordinate of the point
+    pub y: BigUint,
+}
+
+/// Error in elliptic curve primality proving
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EcppError {
+    /// the number is not prime
+    NotPrime,
+    /// no suitable curve was found for some step
+    ProofFailed,
+}
+
+impl fmt::Display for EcppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EcppError::NotPrime => write!(f, "the number is not prime"),
+            EcppError::ProofFailed => write!(f, "no suitable curve was found"),
+        }
+    }
+}
+
+impl std::error::Error for EcppError {}
+
+impl EcppCertificate {
+    /// Verify the certificate without randomized tests
+    ///
+    /// ## Returns
+    ///
+    /// - `true` if the certificate proves that `self.n` is prime
+    /// - `false` otherwise
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use num_bigint::BigUint;
+    /// use yoshi389111_prime_cert::ecpp_certificate;
+    ///
+    /// let n = (BigUint::from(1u8) << 89) - 1u8;
+    /// assert!(ecpp_certificate(&n).unwrap().verify());
+    /// ```
+    pub fn verify(&self) -> bool {
+        let mut n = &self.n;
+        for step in self.steps.iter() {
+            if step.n != *n || !step.verify() {
+                return false;
+            }
+            n = &step.q;
+        }
+        n.to_u64().is_some_and(is_prime_u64)
+    }
+}
+
+impl EcppStep {
+    /// Verify that the step proves `self.n` prime, provided that `self.q` is prime
+    fn verify(&self) -> bool {
+        let n = &self.n;
+        if (n % 6u8) != BigUint::one() && (n % 6u8) != BigUint::from(5u8) {
+            return false;
+        }
+        if self.q >= *n || !exceeds_quartic_bound(&self.q, n) {
+            return false;
+        }
+        let (k, r) = self.m.div_rem(&self.q);
+        if !r.is_zero() {
+            return false;
+        }
+        let curve = Curve {
+            a: self.a.clone(),
+            b: self.b.clone(),
+            n: n.clone(),
+        };
+        let p = Point::Affine(self.x.clone(), self.y.clone());
+        if self.a >= *n || self.b >= *n || !curve.is_nonsingular() || !curve.contains(&p) {
+            return false;
+        }
+        match curve.mul(&p, &k) {
+            Some(Point::Affine(x, y)) => {
+                curve.mul(&Point::Affine(x, y), &self.q) == Some(Point::Infinity)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Build an ECPP primality certificate
+///
+/// Numbers that fit in 64 bits have a certificate without steps.
+/// Each step reduces the number by the factors of the curve order below `2^20`,
+/// and a step that leads to a dead end is replaced with another one.
+/// In a release build, a 1024-bit number takes a few seconds and a 2048-bit number a minute or two.
+///
+/// The text format borrows the layout of Primo certificates, but not their conventions
+/// for rebuilding the curve and the point of a step, which only Primo's verifier follows.
+/// Each step stores its curve and point as they are, so any verifier can check it.
+///
+/// ## Params
+///
+/// - `n`: the number to be certified
+///
+/// ## Returns
+///
+/// - `Ok(certificate)` if `n` is proven prime
+/// - `Err(EcppError::NotPrime)` if `n` is not prime
+/// - `Err(EcppError::ProofFailed)` if no suitable curve was found
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use yoshi389111_prime_cert::{EcppError, ecpp_certificate};
+///
+/// let n = (BigUint::from(1u8) << 89) - 1u8;
+/// let cert = ecpp_certificate(&n).unwrap();
+/// assert_eq!(cert.n, n);
+///
+/// let n = (BigUint::from(1u8) << 89) + 1u8;
+/// assert_eq!(ecpp_certificate(&n), Err(EcppError::NotPrime));
+/// ```
+pub fn ecpp_certificate(n: &BigUint) -> Result<EcppCertificate, EcppError> {
+    if let Some(n64) = n.to_u64() {
+        return if is_prime_u64(n64) {
+            Ok(EcppCertificate {
+                n: n.clone(),
+                steps: Vec::new(),
+            })
+        } else {
+            Err(EcppError::NotPrime)
+        };
+    }
+    if !is_bpsw_prime(n) {
+        return Err(EcppError::NotPrime);
+    }
+
+    let mut steps: Vec<EcppStep> = Vec::new();
+    // the factors `q` that could not be proven prime
+    let mut dead_ends: Vec<BigUint> = Vec::new();
+    let mut current = n.clone();
+    while current.to_u64().is_none() {
+        match find_step(&current, &dead_ends) {
+            Ok(step) => {
+                current = step.q.clone();
+                steps.push(step);
+            }
+            Err(e) => {
+                // go back to the previous step and choose another `q`,
+                // also when a probable prime `q` turned out to be composite
+                let Some(step) = steps.pop() else {
+                    return Err(e);
+                };
+                if dead_ends.len() >= MAX_BACKTRACKS {
+                    return Err(EcppError::ProofFailed);
+                }
+                current = step.n;
+                dead_ends.push(step.q);
+            }
+        }
+    }
+    if !current.to_u64().is_some_and(is_prime_u64) {
+        return Err(EcppError::ProofFailed);
+    }
+    Ok(EcppCertificate {
+        n: n.clone(),
+        steps,
+    })
+}
+
+/// Find a step proving `n` prime with a smaller probable prime `q` not in `dead_ends`
+fn find_step(n: &BigUint, dead_ends: &[BigUint]) -> Result<EcppStep, EcppError> {
+    let roots = SqrtContext::new(n).ok_or(EcppError::NotPrime)?;
+    for disc in DISCRIMINANTS.iter() {
+        if kronecker_small(-(disc.d as i64), n) != 1 {
+            continue;
+        }
+        let Some(t) = cornacchia(disc.d, n, &roots)? else {
+            continue;
+        };
+        for m in [n + 1u8 - &t, n + 1u8 + &t] {
+            let q = remove_small_factors(&m);
+            if q >= *n || !exceeds_quartic_bound(&q, n) || dead_ends.contains(&q) {
+                continue;
+            }
+            if !is_bpsw_prime(&q) {
+                continue;
+            }
+            if let Some(step) = build_step(n, disc, &m, &q, &roots) {
+                return Ok(step);
+            }
+        }
+    }
+    Err(EcppError::ProofFailed)
+}
+
+/// Solve `4n = t^2 + d s^2` by the modified Cornacchia algorithm
+///
+/// ## Returns
+///
+/// - `Ok(Some(t))` if a solution exists
+/// - `Ok(None)` if there is no solution
+/// - `Err(EcppError::NotPrime)` if `n` turned out to be composite
+fn cornacchia(d: u64, n: &BigUint, roots: &SqrtContext) -> Result<Option<BigUint>, EcppError> {
+    let minus_d = (n * 4u8 - d) % n;
+    let mut x = roots.sqrt(&minus_d).ok_or(EcppError::NotPrime)?;
+    // x = -d (mod 2)
+    if x.is_even() != d.is_multiple_of(2) {
+        x = n - x;
+    }
+    let four_n: BigUint = n << 2;
+    let limit = four_n.sqrt();
+    let (mut a, mut b) = (n << 1, x);
+    while b > limit {
+        let r = &a % &b;
+        a = b;
+        b = r;
+    }
+    let rest = &four_n - &b * &b;
+    let (s2, r) = rest.div_rem(&BigUint::from(d));
+    if !r.is_zero() {
+        return Ok(None);
+    }
+    let s = s2.sqrt();
+    Ok((&s * &s == s2).then_some(b))
+}
+
+/// Remove the prime factors below `MAX_SMALL_FACTOR` from `m`
+///
+/// The factors are found by a gcd with their product, instead of a division by each prime.
+fn remove_small_factors(m: &BigUint) -> BigUint {
+    let mut q = m.clone();
+    let mut g = (&*SMALL_PRIMORIAL % &q).gcd(&q);
+    while !g.is_one() {
+        q /= &g;
+        g = g.gcd(&q);
+    }
+    q
+}
+
+/// Check `q > (n^(1/4) + 1)^2`, with `(floor(sqrt(q)) - 1)^4 > n`
+fn exceeds_quartic_bound(q: &BigUint, n: &BigUint) -> bool {
+    let s = q.sqrt();
+    s > BigUint::one() && (s - 1u8).pow(4) > *n
+}
+
+/// Build a curve with `m` points and a point proving `n` prime
+///
+/// Returns `None` if the discriminant is not usable.
+fn build_step(
+    n: &BigUint,
+    disc: &Discriminant,
+    m: &BigUint,
+    q: &BigUint,
+    roots: &SqrtContext,
+) -> Option<EcppStep> {
+    let poly = disc
+        .poly
+        .get_or_init(|| hilbert_class_polynomial(disc.d, &disc.forms))
+        .as_ref()?;
+    let n_int = BigInt::from(n.clone());
+    let poly: Vec<BigUint> = poly
+        .iter()
+        .map(|c| c.mod_floor(&n_int).to_biguint().expect("non-negative"))
+        .collect();
+    let j = find_root(&poly, n)?;
+    let j_1728 = (BigUint::from(1728u16) + n - &j) % n;
+    if j.is_zero() {
+        return None;
+    }
+    // the curve y^2 = x^3 + 3k x + 2k with k = j / (1728 - j) has the j-invariant j
+    let k = &j * j_1728.modinv(n)? % n;
+    let a = &k * 3u8 % n;
+    let b = &k * 2u8 % n;
+    let c = roots.non_residue();
+    let twist_a = &a * c % n * c % n;
+    let twist_b = &b * c % n * c % n * c % n;
+
+    let cofactor = m / q;
+    for (a, b) in [(a, b), (twist_a, twist_b)] {
+        let curve = Curve { a, b, n: n.clone() };
+        for x in 0..MAX_POINTS {
+            let x = BigUint::from(x);
+            let Some(y) = roots.sqrt(&curve.rhs(&x)) else {
+                continue;
+            };
+            let p = Point::Affine(x.clone(), y.clone());
+            let r = match curve.mul(&p, &cofactor) {
+                Some(r @ Point::Affine(..)) => r,
+                _ => continue,
+            };
+            if curve.mul(&r, q) != Some(Point::Infinity) {
+                // the curve has the other number of points
+                break;
+            }
+            return Some(EcppStep {
+                n: n.clone(),
+                a: curve.a,
+                b: curve.b,
+                m: m.clone(),
+                q: q.clone(),
+                x,
+                y,
+            });
+        }
+    }
+    None
+}
+
+/// Sieve the squarefree numbers up to `max`
+fn squarefree_sieve(max: u64) -> Vec<bool> {
+    let max = max as usize;
+    let mut squarefree = vec![true; max + 1];
+    let mut p = 2;
+    while p * p <= max {
+        for multiple in (p * p..=max).step_by(p * p) {
+            squarefree[multiple] = false;
+        }
+        p += 1;
+    }
+    squarefree
+}
+
+/// Check if `-d` is a fundamental discriminant, with the squarefree numbers up to `d`
+fn is_fundamental_discriminant(d: u64, squarefree: &[bool]) -> bool {
+    match d % 4 {
+        3 => squarefree[d as usize],
+        0 => matches!((d / 4) % 4, 1 | 2) && squarefree[(d / 4) as usize],
+        _ => false,
+    }
+}
+
+impl fmt::Display for EcppCertificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[ECPP - Primality Certificate]")?;
+        writeln!(f, "Format=1")?;
+        writeln!(f)?;
+        writeln!(f, "[Candidate]")?;
+        writeln!(f, "N=${:X}", self.n)?;
+        for (i, step) in self.steps.iter().enumerate() {
+            writeln!(f)?;
+            writeln!(f, "[{}]", i + 1)?;
+            writeln!(f, "N=${:X}", step.n)?;
+            writeln!(f, "A=${:X}", step.a)?;
+            writeln!(f, "B=${:X}", step.b)?;
+            writeln!(f, "M=${:X}", step.m)?;
+            writeln!(f, "Q=${:X}", step.q)?;
+            writeln!(f, "X=${:X}", step.x)?;
+            writeln!(f, "Y=${:X}", step.y)?;
+        }
+        Ok(())
+    }
+}
+
+/// A section of the text format
+struct Section<'a> {
+    /// the line number (one-based) of the header
+    line_no: usize,
+    /// the name in the header
+    name: &'a str,
+    /// the key-value pairs
+    values: Vec<(&'a str, BigUint)>,
+}
+
+impl Section<'_> {
+    /// Get the value of the key
+    fn get(&self, key: &str) -> Result<BigUint, ParseCertificateError> {
+        self.values
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.clone())
+            .ok_or(ParseCertificateError::InvalidLine(self.line_no))
+    }
+}
+
+impl FromStr for EcppCertificate {
+    type Err = ParseCertificateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut sections: Vec<Section> = Vec::new();
+        for (index, line) in s.lines().enumerate() {
+            let line_no = index + 1;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                sections.push(Section {
+                    line_no,
+                    name,
+                    values: Vec::new(),
+                });
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or(ParseCertificateError::InvalidLine(line_no))?;
+            let Some(section) = sections.last_mut() else {
+                return Err(ParseCertificateError::InvalidLine(line_no));
+            };
+            if section.name == "ECPP - Primality Certificate" {
+                continue;
+            }
+            let value = value
+                .strip_prefix('$')
+                .and_then(|hex| BigUint::parse_bytes(hex.as_bytes(), 16))
+                .ok_or(ParseCertificateError::InvalidLine(line_no))?;
+            section.values.push((key, value));
+        }
+
+        let candidate = sections
+            .iter()
+            .find(|section| section.name == "Candidate")
+            .ok_or(ParseCertificateError::Empty)?;
+        let n = candidate.get("N")?;
+        let mut steps = Vec::new();
+        for section in sections.iter() {
+            if section.name != (steps.len() + 1).to_string() {
+                if section.name.parse::<usize>().is_ok() {
+                    return Err(ParseCertificateError::InvalidLine(section.line_no));
+                }
+                continue;
+            }
+            steps.push(EcppStep {
+                n: section.get("N")?,
+                a: section.get("A")?,
+                b: section.get("B")?,
+                m: section.get("M")?,
+                q: section.get("Q")?,
+                x: section.get("X")?,
+                y: section.get("Y")?,
+            });
+        }
+        Ok(EcppCertificate { n, steps })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yoshi389111_prime_gen::{TopBits, random_prime_with_rng};
+
+    #[test]
+    fn ecpp_certificate_verifies() {
+        let n = (BigUint::one() << 127) - 1u8;
+        let cert = ecpp_certificate(&n).unwrap();
+        assert!(!cert.steps.is_empty());
+        assert!(cert.verify());
+
+        let n = BigUint::from(18_446_744_073_709_551_557_u64);
+        let cert = ecpp_certificate(&n).unwrap();
+        assert!(cert.steps.is_empty() && cert.verify());
+    }
+
+    #[test]
+    #[ignore = "slow; run with `cargo test --release -- --ignored`"]
+    fn ecpp_certificate_of_random_1024_bit_primes() {
+        let mut rng = <rand::rngs::StdRng as rand::SeedableRng>::seed_from_u64(389_111);
+        for _ in 0..3 {
+            let n = random_prime_with_rng(1024, TopBits::One, 20, &mut rng);
+            let cert = ecpp_certificate(&n).unwrap();
+            assert_eq!(cert.n, n);
+            assert!(cert.verify());
+        }
+    }
+
+    #[test]
+    #[ignore = "slow; run with `cargo test --release -- --ignored`"]
+    fn ecpp_certificate_of_random_2048_bit_prime() {
+        let mut rng = <rand::rngs::StdRng as rand::SeedableRng>::seed_from_u64(389_111);
+        let n = random_prime_with_rng(2048, TopBits::One, 20, &mut rng);
+        let cert = ecpp_certificate(&n).unwrap();
+        assert_eq!(cert.n, n);
+        assert!(cert.verify());
+    }
+
+    #[test]
+    fn ecpp_certificate_with_composite() {
+        let p = BigUint::from(18_446_744_073_709_551_557_u64);
+        let q = BigUint::from(4_294_967_291_u64);
+        assert_eq!(ecpp_certificate(&(&p * &q)), Err(EcppError::NotPrime));
+        assert_eq!(ecpp_certificate(&BigUint::one()), Err(EcppError::NotPrime));
+    }
+
+    #[test]
+    fn tampered_certificate_is_rejected() {
+        let n = (BigUint::one() << 107) - 1u8;
+        let mut cert = ecpp_certificate(&n).unwrap();
+        cert.steps[0].x += 1u8;
+        assert!(!cert.verify());
+
+        let mut cert = ecpp_certificate(&n).unwrap();
+        cert.n += 2u8;
+        assert!(!cert.verify());
+    }
+
+    #[test]
+    fn text_format_round_trip() {
+        let n = (BigUint::one() << 107) - 1u8;
+        let cert = ecpp_certificate(&n).unwrap();
+        let text = cert.to_string();
+        assert!(
+            text.starts_with("[ECPP - Primality Certificate]\nFormat=1\n\n[Candidate]\nN=$7FF")
+        );
+        assert_eq!(text.parse::<EcppCertificate>(), Ok(cert));
+        assert_eq!(
+            "[Candidate]\nN=12".parse::<EcppCertificate>(),
+            Err(ParseCertificateError::InvalidLine(2))
+        );
+    }
+}
@@ -0,0 +1,187 @@
+//! Elliptic curves `y^2 = x^3 + a x + b` over `Z/nZ`
+//!
+//! Scalar multiplications are computed in Jacobian coordinates `(X : Y : Z)`, `x = X / Z^2`,
+//! `y = Y / Z^3`, without inversions, and the result is converted back to affine coordinates.
+//! The point at infinity is `(t^2 : t^3 : 0)` with `t != 0`.
+//!
+//! The formulas are applied to every intermediate point without distinguishing cases, so the
+//! computation modulo a prime factor `p` of `n` agrees with the group law modulo `p`, except
+//! when an addition meets the point at infinity or adds a point to itself. In both exceptional
+//! cases the result is `(0 : 0 : 0)` modulo `p`, and it stays so until the end.
+//! Since `Y` of the point at infinity is invertible, `(0 : 0 : 0)` is told apart from it,
+//! and a result that is not the same point modulo every prime factor of `n` is rejected.
+
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::{One, Zero};
+
+/// A point on an elliptic curve
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Point {
+    /// The point at infinity
+    Infinity,
+    /// A point `(x, y)`
+    Affine(BigUint, BigUint),
+}
+
+/// A point `(X : Y : Z)` in Jacobian coordinates
+struct Jacobian {
+    x: BigUint,
+    y: BigUint,
+    z: BigUint,
+}
+
+/// An elliptic curve `y^2 = x^3 + a x + b` over `Z/nZ`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Curve {
+    /// the coefficient `a`
+    pub(crate) a: BigUint,
+    /// the coefficient `b`
+    pub(crate) b: BigUint,
+    /// the modulus `n`
+    pub(crate) n: BigUint,
+}
+
+impl Curve {
+    /// Check if `4 a^3 + 27 b^2` is invertible modulo `n`
+    pub(crate) fn is_nonsingular(&self) -> bool {
+        let n = &self.n;
+        let disc = (4u8 * self.a.modpow(&BigUint::from(3u8), n) + 27u8 * &self.b * &self.b) % n;
+        disc.modinv(n).is_some()
+    }
+
+    /// Calculate `x^3 + a x + b mod n`
+    pub(crate) fn rhs(&self, x: &BigUint) -> BigUint {
+        (x * x * x + &self.a * x + &self.b) % &self.n
+    }
+
+    /// Check if the point is on the curve
+    pub(crate) fn contains(&self, p: &Point) -> bool {
+        match p {
+            Point::Infinity => true,
+            Point::Affine(x, y) => x < &self.n && y < &self.n && (y * y) % &self.n == self.rhs(x),
+        }
+    }
+
+    /// Calculate `a - b mod n` for `a, b < n`
+    fn sub(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        (a + &self.n - b) % &self.n
+    }
+
+    /// Double a point
+    fn double(&self, p: &Jacobian) -> Jacobian {
+        let n = &self.n;
+        let yy = &p.y * &p.y % n;
+        let zz = &p.z * &p.z % n;
+        let s = 4u8 * &p.x * &yy % n;
+        let m = (3u8 * &p.x * &p.x + &self.a * &zz % n * &zz) % n;
+        let x = self.sub(&(&m * &m % n), &(2u8 * &s % n));
+        let y = self.sub(&(&m * self.sub(&s, &x) % n), &(8u8 * &yy * &yy % n));
+        let z = 2u8 * &p.y * &p.z % n;
+        Jacobian { x, y, z }
+    }
+
+    /// Add an affine point `(x2, y2)` to a point
+    fn add_affine(&self, p: &Jacobian, x2: &BigUint, y2: &BigUint) -> Jacobian {
+        let n = &self.n;
+        let zz = &p.z * &p.z % n;
+        let h = self.sub(&(x2 * &zz % n), &p.x);
+        let r = self.sub(&(y2 * &zz % n * &p.z % n), &p.y);
+        let hh = &h * &h % n;
+        let hhh = &h * &hh % n;
+        let v = &p.x * &hh % n;
+        let x = self.sub(&(&r * &r % n), &((&hhh + 2u8 * &v) % n));
+        let y = self.sub(&(&r * self.sub(&v, &x) % n), &(&p.y * &hhh % n));
+        let z = &p.z * &h % n;
+        Jacobian { x, y, z }
+    }
+
+    /// Multiply a point by a scalar (double-and-add)
+    ///
+    /// ## Returns
+    ///
+    /// - `Some(k * p)` if the result is the same point modulo every prime factor of `n`
+    /// - `None` otherwise, which means that `n` is composite, or that an intermediate point
+    ///   was the point at infinity or `p` (and then `k` is not less than the order of `p`)
+    pub(crate) fn mul(&self, p: &Point, k: &BigUint) -> Option<Point> {
+        let n = &self.n;
+        let Point::Affine(x, y) = p else {
+            return Some(Point::Infinity);
+        };
+        if k.is_zero() {
+            return Some(Point::Infinity);
+        }
+        let mut result = Jacobian {
+            x: x.clone(),
+            y: y.clone(),
+            z: BigUint::one(),
+        };
+        for i in (0..k.bits() - 1).rev() {
+            result = self.double(&result);
+            if k.bit(i) {
+                result = self.add_affine(&result, x, y);
+            }
+        }
+        if result.z.is_zero() {
+            return result.y.gcd(n).is_one().then_some(Point::Infinity);
+        }
+        let z_inv = result.z.modinv(n)?;
+        let z_inv2 = &z_inv * &z_inv % n;
+        let x = result.x * &z_inv2 % n;
+        let y = result.y * z_inv2 % n * z_inv % n;
+        Some(Point::Affine(x, y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_order_annihilates_points() {
+        // y^2 = x^3 + 2x + 3 over F_97 has 100 points, and (0, 10) is of order 50
+        let curve = Curve {
+            a: BigUint::from(2u8),
+            b: BigUint::from(3u8),
+            n: BigUint::from(97u8),
+        };
+        let p = Point::Affine(BigUint::from(0u8), BigUint::from(10u8));
+        assert!(curve.contains(&p));
+        assert_eq!(curve.mul(&p, &BigUint::from(100u8)), Some(Point::Infinity));
+        assert_eq!(curve.mul(&p, &BigUint::one()), Some(p.clone()));
+        let Some(Point::Affine(x, y)) = curve.mul(&p, &BigUint::from(7u8)) else {
+            panic!("7 P is not an affine point");
+        };
+        assert!(curve.contains(&Point::Affine(x.clone(), y.clone())));
+        // 93 P = -7 P
+        assert_eq!(
+            curve.mul(&p, &BigUint::from(93u8)),
+            Some(Point::Affine(x, BigUint::from(97u8) - y))
+        );
+    }
+
+    #[test]
+    fn composite_modulus_is_detected() {
+        // y^2 = x^3 + x + 1 has 9 points modulo 5 and 5 points modulo 7,
+        // so 5 (0, 1) is the point at infinity modulo 7 only
+        let curve = Curve {
+            a: BigUint::from(1u8),
+            b: BigUint::from(1u8),
+            n: BigUint::from(35u8),
+        };
+        let p = Point::Affine(BigUint::from(0u8), BigUint::from(1u8));
+        assert_eq!(curve.mul(&p, &BigUint::from(5u8)), None);
+    }
+
+    #[test]
+    fn exceptional_additions_are_rejected() {
+        // 100 P = O is passed on the way to 101 P = P
+        let curve = Curve {
+            a: BigUint::from(2u8),
+            b: BigUint::from(3u8),
+            n: BigUint::from(97u8),
+        };
+        let p = Point::Affine(BigUint::from(0u8), BigUint::from(10u8));
+        assert_eq!(curve.mul(&p, &BigUint::from(101u8)), None);
+    }
+}
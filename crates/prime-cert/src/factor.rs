@@ -0,0 +1,150 @@
+//! Integer factorization with trial division and Pollard's rho method
+//!
+//! ## References
+//!
+//! - R. P. Brent, "An improved Monte Carlo factorization algorithm", BIT 20 (1980)
+
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::{One, Zero};
+use once_cell::sync::Lazy;
+use yoshi389111_miller_rabin::is_probable_prime;
+
+/// Maximum prime number for trial division
+const MAX_SMALL_PRIME: u32 = 10_000;
+
+/// Number of products accumulated before taking a gcd in Brent's method
+const GCD_BATCH: usize = 128;
+
+/// Number of Miller-Rabin rounds for the factors
+const ROUNDS: usize = 40;
+
+/// A list of small prime numbers for trial division
+static PRIMES: Lazy<Vec<u32>> = Lazy::new(|| {
+    yoshi389111_prime_iter::new::<u32>()
+        .take_while(|p| *p <= MAX_SMALL_PRIME)
+        .collect()
+});
+
+/// Factorize `n` into primes
+///
+/// The factors found by Pollard's rho method are checked by the Miller-Rabin test,
+/// so they are only probably prime.
+///
+/// ## Returns
+///
+/// - pairs of a prime factor and its exponent, in ascending order of the factors
+pub(crate) fn factorize(n: &BigUint) -> Vec<(BigUint, u32)> {
+    let mut factors = Vec::new();
+    let mut n = n.clone();
+    for &p in PRIMES.iter() {
+        let p = BigUint::from(p);
+        if &p * &p > n {
+            break;
+        }
+        let mut exponent = 0;
+        while (&n % &p).is_zero() {
+            n /= &p;
+            exponent += 1;
+        }
+        if exponent > 0 {
+            factors.push((p, exponent));
+        }
+    }
+
+    let mut stack = vec![n];
+    while let Some(m) = stack.pop() {
+        if m.is_one() {
+            continue;
+        }
+        if is_probable_prime(&m, ROUNDS) {
+            match factors.iter_mut().find(|(p, _)| *p == m) {
+                Some((_, exponent)) => *exponent += 1,
+                None => factors.push((m, 1)),
+            }
+            continue;
+        }
+        let d = pollard_rho(&m);
+        stack.push(&m / &d);
+        stack.push(d);
+    }
+    factors.sort();
+    factors
+}
+
+/// Find a nontrivial factor of an odd composite `n` using Brent's variant of Pollard's rho method
+fn pollard_rho(n: &BigUint) -> BigUint {
+    let f = |x: &BigUint, c: u32| (x * x + c) % n;
+    for c in 1u32.. {
+        let mut y = BigUint::from(2u8);
+        let mut x = y.clone();
+        let mut ys = y.clone();
+        let mut q = BigUint::one();
+        let mut g = BigUint::one();
+        let mut r = 1usize;
+        while g.is_one() {
+            x = y.clone();
+            for _ in 0..r {
+                y = f(&y, c);
+            }
+            let mut k = 0;
+            while k < r && g.is_one() {
+                ys = y.clone();
+                for _ in 0..GCD_BATCH.min(r - k) {
+                    y = f(&y, c);
+                    q = q * abs_diff(&x, &y) % n;
+                }
+                g = q.gcd(n);
+                k += GCD_BATCH;
+            }
+            r *= 2;
+        }
+        if &g == n {
+            // the batch overshot; retry one step at a time
+            loop {
+                ys = f(&ys, c);
+                g = abs_diff(&x, &ys).gcd(n);
+                if !g.is_one() {
+                    break;
+                }
+            }
+        }
+        if &g != n {
+            return g;
+        }
+    }
+    unreachable!("a factor is always found for a composite number")
+}
+
+/// Calculate `|a - b|`
+fn abs_diff(a: &BigUint, b: &BigUint) -> BigUint {
+    if a > b { a - b } else { b - a }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn factorize_with_small_and_large_factors() {
+        let p = BigUint::from(1_000_000_007_u32);
+        let q = BigUint::from(4_294_967_291_u64);
+        let n = BigUint::from(2u8).pow(5) * 9973u32 * &p * &p * &q;
+        assert_eq!(
+            factorize(&n),
+            vec![
+                (BigUint::from(2u8), 5),
+                (BigUint::from(9973u32), 1),
+                (p, 2),
+                (q, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn factorize_with_one_and_prime() {
+        assert_eq!(factorize(&BigUint::one()), vec![]);
+        let p = BigUint::from(18_446_744_073_709_551_557_u64);
+        assert_eq!(factorize(&p), vec![(p, 1)]);
+    }
+}
@@ -0,0 +1,347 @@
+//! Hilbert class polynomials of imaginary quadratic discriminants
+//!
+//! `H_D(X)` is the product of `X - j(tau)` over the reduced primitive forms `(a, b, c)`
+//! of discriminant `D`, with `tau = (-b + sqrt(D)) / (2a)`.
+//! The j-invariants are evaluated with fixed-point complex arithmetic, using
+//! `j = (256 f + 1)^3 / f` for `f = Delta(2 tau) / Delta(tau)`, where the Dedekind eta
+//! products are summed with the pentagonal number theorem.
+//!
+//! ## References
+//!
+//! - H. Cohen, "A Course in Computational Algebraic Number Theory", Algorithm 7.6.1
+//! - A. Enge, "The complexity of class polynomial computation via floating point approximations",
+//!   Math. Comp. 78 (2009)
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{One, Signed, Zero};
+
+/// Guard bits of the fixed-point arithmetic
+const GUARD_BITS: u64 = 64;
+
+/// A primitive form `(a, b, c)`, represented by `(a, b)` since `c` follows from the discriminant
+pub(crate) type Form = (u64, i64);
+
+/// Calculate the reduced forms `(a, b)` of the discriminants `-d` with `d <= max_d` at once
+///
+/// A form `(a, b, c)` with `b^2 - 4ac = -d` is reduced if `|b| <= a <= c`,
+/// and `b >= 0` if `|b| = a` or `a = c`.
+/// The forms are enumerated by `(a, b, c)` instead of `d`, which is much faster than
+/// solving for `c` for each discriminant. Non-primitive forms are included,
+/// but they do not exist for fundamental discriminants.
+///
+/// ## Returns
+///
+/// - `(d, forms)` in ascending order of `d`, for the discriminants with `keep(d, forms.len())`
+pub(crate) fn reduced_forms_up_to<F>(max_d: u64, keep: F) -> Vec<(u64, Vec<Form>)>
+where
+    F: Fn(u64, usize) -> bool,
+{
+    let max_d = max_d as usize;
+    let mut counts = vec![0usize; max_d + 1];
+    for_each_reduced_form(max_d, |d, _| counts[d] += 1);
+
+    let mut index = vec![usize::MAX; max_d + 1];
+    let mut result = Vec::new();
+    for (d, &count) in counts.iter().enumerate() {
+        if count > 0 && keep(d as u64, count) {
+            index[d] = result.len();
+            result.push((d as u64, Vec::with_capacity(count)));
+        }
+    }
+    for_each_reduced_form(max_d, |d, form| {
+        if let Some((_, forms)) = result.get_mut(index[d]) {
+            forms.push(form);
+        }
+    });
+    result
+}
+
+/// Call `f(d, (a, b))` for each reduced form of discriminant `-d` with `d <= max_d`,
+/// in ascending order of `(a, b)`
+fn for_each_reduced_form<F>(max_d: usize, mut f: F)
+where
+    F: FnMut(usize, Form),
+{
+    let mut a = 1usize;
+    while 3 * a * a <= max_d {
+        for b in -(a as i64) + 1..=a as i64 {
+            let b2 = b.unsigned_abs() as usize * b.unsigned_abs() as usize;
+            let mut c = if b < 0 { a + 1 } else { a };
+            while 4 * a * c - b2 <= max_d {
+                f(4 * a * c - b2, (a as u64, b));
+                c += 1;
+            }
+        }
+        a += 1;
+    }
+}
+
+/// Calculate the Hilbert class polynomial of discriminant `-d`
+///
+/// ## Returns
+///
+/// - `Some(coefficients)` in ascending order of the degree
+/// - `None` if the precision turned out to be insufficient
+pub(crate) fn hilbert_class_polynomial(d: u64, forms: &[Form]) -> Option<Vec<BigInt>> {
+    // log2 of the bound of the coefficients: |j| <= e^(pi sqrt(d) / a) + 2079
+    let log2_j = |a: u64| {
+        std::f64::consts::PI * (d as f64).sqrt() / (a as f64) / std::f64::consts::LN_2 + 1.0
+    };
+    let coefficient_bits: f64 = forms.iter().map(|&(a, _)| log2_j(a) + 1.0).sum();
+    let prec = 2 * coefficient_bits.ceil() as u64 + GUARD_BITS;
+    let ctx = Fixed::new(prec);
+
+    let mut poly = vec![ctx.one()];
+    for &(a, b) in forms.iter() {
+        let j = ctx.j_invariant(d, a, b);
+        // poly *= (X - j)
+        let mut next = vec![Complex::zero(); poly.len() + 1];
+        for (i, c) in poly.iter().enumerate() {
+            next[i + 1] = next[i + 1].add(c);
+            next[i] = next[i].sub(&ctx.mul(c, &j));
+        }
+        poly = next;
+    }
+
+    let tolerance = BigInt::one() << (prec - 16);
+    poly.iter()
+        .map(|c| {
+            let rounded = (&c.re + (BigInt::one() << (prec - 1))) >> prec;
+            let error = &c.re - (&rounded << prec);
+            (error.abs() < tolerance && c.im.abs() < tolerance).then_some(rounded)
+        })
+        .collect()
+}
+
+/// Complex number in fixed point
+#[derive(Debug, Clone)]
+struct Complex {
+    re: BigInt,
+    im: BigInt,
+}
+
+impl Complex {
+    fn zero() -> Self {
+        Complex {
+            re: BigInt::zero(),
+            im: BigInt::zero(),
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.re.is_zero() && self.im.is_zero()
+    }
+
+    fn add(&self, other: &Complex) -> Complex {
+        Complex {
+            re: &self.re + &other.re,
+            im: &self.im + &other.im,
+        }
+    }
+
+    fn sub(&self, other: &Complex) -> Complex {
+        Complex {
+            re: &self.re - &other.re,
+            im: &self.im - &other.im,
+        }
+    }
+
+    fn shl(&self, bits: u64) -> Complex {
+        Complex {
+            re: &self.re << bits,
+            im: &self.im << bits,
+        }
+    }
+
+    fn shr(&self, bits: u64) -> Complex {
+        Complex {
+            re: &self.re >> bits,
+            im: &self.im >> bits,
+        }
+    }
+}
+
+/// Fixed-point arithmetic with `prec` fractional bits
+struct Fixed {
+    prec: u64,
+}
+
+impl Fixed {
+    fn new(prec: u64) -> Self {
+        Fixed { prec }
+    }
+
+    /// The number one
+    fn one(&self) -> Complex {
+        Complex {
+            re: BigInt::one() << self.prec,
+            im: BigInt::zero(),
+        }
+    }
+
+    /// A real number
+    fn real(&self, re: BigInt) -> Complex {
+        Complex {
+            re,
+            im: BigInt::zero(),
+        }
+    }
+
+    fn mul(&self, a: &Complex, b: &Complex) -> Complex {
+        Complex {
+            re: (&a.re * &b.re - &a.im * &b.im) >> self.prec,
+            im: (&a.re * &b.im + &a.im * &b.re) >> self.prec,
+        }
+    }
+
+    fn div(&self, a: &Complex, b: &Complex) -> Complex {
+        let norm = &b.re * &b.re + &b.im * &b.im;
+        Complex {
+            re: ((&a.re * &b.re + &a.im * &b.im) << self.prec) / &norm,
+            im: ((&a.im * &b.re - &a.re * &b.im) << self.prec) / &norm,
+        }
+    }
+
+    /// `pi` by Machin's formula `pi / 4 = 4 arctan(1/5) - arctan(1/239)`
+    fn pi(&self) -> BigInt {
+        let arctan_inv = |x: u32| {
+            let x2 = BigInt::from(x) * x;
+            let mut power = (BigInt::one() << self.prec) / x;
+            let mut sum = BigInt::zero();
+            let mut k = 0u32;
+            while !power.is_zero() {
+                let term = &power / (2 * k + 1);
+                if k.is_even() {
+                    sum += term;
+                } else {
+                    sum -= term;
+                }
+                power /= &x2;
+                k += 1;
+            }
+            sum
+        };
+        (arctan_inv(5) * 4 - arctan_inv(239)) * 4
+    }
+
+    /// The exponential function, with `|z|` reduced by halving before the Taylor series
+    fn exp(&self, z: &Complex) -> Complex {
+        let size = z.re.bits().max(z.im.bits());
+        let halvings = (size + 1).saturating_sub(self.prec);
+        let inner = Fixed::new(self.prec + halvings + GUARD_BITS);
+        // z / 2^halvings in the inner precision
+        let z = z.shl(GUARD_BITS);
+
+        let mut sum = inner.one();
+        let mut term = inner.one();
+        let mut i = 1u32;
+        while !term.is_zero() {
+            term = inner.mul(&term, &z);
+            term.re /= i;
+            term.im /= i;
+            sum = sum.add(&term);
+            i += 1;
+        }
+        for _ in 0..halvings {
+            sum = inner.mul(&sum, &sum);
+        }
+        sum.shr(halvings + GUARD_BITS)
+    }
+
+    /// Euler's function `prod (1 - x^n)` by the pentagonal number theorem
+    fn euler_phi(&self, x: &Complex) -> Complex {
+        let x3 = self.mul(&self.mul(x, x), x);
+        let mut sum = self.one();
+        let mut power = x.clone(); // x^(k(3k-1)/2)
+        let mut x_k = x.clone(); // x^k
+        let mut step = self.mul(&x3, x); // x^(3k+1)
+        let mut k = 1u32;
+        while !power.is_zero() {
+            let terms = power.add(&self.mul(&power, &x_k));
+            sum = if k.is_even() {
+                sum.add(&terms)
+            } else {
+                sum.sub(&terms)
+            };
+            power = self.mul(&power, &step);
+            step = self.mul(&step, &x3);
+            x_k = self.mul(&x_k, x);
+            k += 1;
+        }
+        sum
+    }
+
+    /// The j-invariant `j(tau)` for `tau = (-b + sqrt(-d)) / (2a)`
+    fn j_invariant(&self, d: u64, a: u64, b: i64) -> Complex {
+        let pi = self.pi();
+        let sqrt_d = (BigInt::from(d) << (2 * self.prec)).sqrt();
+        // 1 / q = exp(-2 pi i tau) = exp((pi sqrt(d) + i pi b) / a)
+        let z = Complex {
+            re: ((&pi * sqrt_d) >> self.prec) / a,
+            im: &pi * b / a,
+        };
+        let q_inv = self.exp(&z);
+        let q = self.div(&self.one(), &q_inv);
+
+        // 1 / f = (1 / q) * (phi(q) / phi(q^2))^24
+        let ratio = self.div(&self.euler_phi(&q), &self.euler_phi(&self.mul(&q, &q)));
+        let mut ratio24 = ratio;
+        for _ in 0..3 {
+            ratio24 = self.mul(&ratio24, &ratio24);
+        }
+        let ratio24 = self.mul(&ratio24, &self.mul(&ratio24, &ratio24));
+        let f_inv = self.mul(&q_inv, &ratio24);
+        let f = self.div(&self.one(), &f_inv);
+
+        // j = (256 f + 1)^3 / f = 1 / f + 768 + 3 * 256^2 f + 256^3 f^2
+        let f2 = self.mul(&f, &f);
+        f_inv
+            .add(&self.real(BigInt::from(768) << self.prec))
+            .add(&self.mul(&self.real(BigInt::from(196_608) << self.prec), &f))
+            .add(&self.mul(&self.real(BigInt::from(16_777_216) << self.prec), &f2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forms(d: u64) -> Vec<Form> {
+        reduced_forms_up_to(d, |x, _| x == d).pop().unwrap().1
+    }
+
+    fn class_polynomial(d: u64) -> Vec<BigInt> {
+        hilbert_class_polynomial(d, &forms(d)).unwrap()
+    }
+
+    #[test]
+    fn class_polynomials_of_small_discriminants() {
+        let poly = |coefficients: &[i64]| {
+            coefficients
+                .iter()
+                .map(|&c| BigInt::from(c))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(class_polynomial(7), poly(&[3375, 1]));
+        assert_eq!(class_polynomial(163), poly(&[262_537_412_640_768_000, 1]));
+        assert_eq!(class_polynomial(15), poly(&[-121_287_375, 191_025, 1]));
+        assert_eq!(
+            class_polynomial(23),
+            poly(&[12_771_880_859_375, -5_151_296_875, 3_491_750, 1])
+        );
+    }
+
+    #[test]
+    fn reduced_forms_count_class_numbers() {
+        assert_eq!(forms(163).len(), 1);
+        assert_eq!(forms(23), vec![(1, 1), (2, -1), (2, 1)]);
+        assert_eq!(forms(71).len(), 7);
+        assert_eq!(forms(199).len(), 9);
+        // (2, 2, 2) is not primitive
+        assert_eq!(forms(12), vec![(1, 0), (2, 2)]);
+        let all = reduced_forms_up_to(2000, |_, _| true);
+        assert_eq!(all.len(), 1000);
+        assert!(all.iter().all(|(d, forms)| forms[0] == (1, (d % 2) as i64)));
+    }
+}
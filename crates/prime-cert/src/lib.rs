@@ -0,0 +1,74 @@
+//! Primality certificates
+//!
+//! This crate proves that a number is prime, instead of declaring it "probably prime".
+//! A certificate can be stored next to the number, and verified later without randomized tests.
+//!
+//! ## Features
+//!
+//! - Pratt certificates, with a recursive factorization of `p - 1`
+//! - Pocklington and Brillhart-Lehmer-Selfridge proofs with a partial factorization of `n - 1`
+//! - Elliptic curve primality proving (ECPP), which needs no factorization of `n - 1`
+//! - Text format of the certificates; the ECPP format borrows the layout of Primo certificates,
+//!   but stores each curve and point as they are, so it is not readable by Primo (see [`ecpp_certificate`])
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use num_bigint::BigUint;
+//! use yoshi389111_prime_cert::{PrattCertificate, pratt_certificate};
+//!
+//! let p = BigUint::from(389_111_u64);
+//! let text = pratt_certificate(&p).unwrap().to_string();
+//! let cert: PrattCertificate = text.parse().unwrap();
+//! assert!(cert.verify());
+//! ```
+//!
+//! ## References
+//!
+//! - V. R. Pratt, "Every prime has a succinct certificate", SIAM J. Comput. 4 (1975)
+//! - J. Brillhart, D. H. Lehmer and J. L. Selfridge, "New primality criteria and factorizations of 2^m ± 1",
+//!   Math. Comp. 29 (1975)
+//! - A. O. L. Atkin and F. Morain, "Elliptic curves and primality proving", Math. Comp. 61 (1993)
+
+mod ecpp;
+mod elliptic;
+mod factor;
+mod hilbert;
+mod modular;
+mod n_minus_one;
+mod polynomial;
+mod pratt;
+pub use crate::ecpp::{EcppCertificate, EcppError, EcppStep, ecpp_certificate};
+pub use crate::n_minus_one::{
+    NMinusOneCertificate, NMinusOneError, NMinusOneFactor, n_minus_one_certificate,
+};
+pub use crate::pratt::{PrattCertificate, pratt_certificate};
+
+use std::fmt;
+
+/// Error in parsing the text format of a certificate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseCertificateError {
+    /// The text has no certificate
+    Empty,
+    /// The line (one-based) is malformed
+    InvalidLine(usize),
+    /// The line (one-based) refers to a factor without a preceding certificate
+    UnknownFactor(usize),
+}
+
+impl fmt::Display for ParseCertificateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseCertificateError::Empty => write!(f, "the certificate is empty"),
+            ParseCertificateError::InvalidLine(line) => {
+                write!(f, "invalid certificate at line {line}")
+            }
+            ParseCertificateError::UnknownFactor(line) => {
+                write!(f, "uncertified factor at line {line}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseCertificateError {}
@@ -0,0 +1,192 @@
+//! Modular arithmetic modulo a (probable) prime
+
+use num_bigint::BigUint;
+use num_traits::{One, ToPrimitive, Zero};
+
+/// Maximum number of candidates tried as a quadratic non-residue
+const MAX_NON_RESIDUE: u32 = 1000;
+
+/// Calculate the Kronecker symbol `(d/n)` of a small `d` and an odd `n`
+///
+/// Only `n mod 8` and `n mod |d|` are needed, thanks to the quadratic reciprocity.
+pub(crate) fn kronecker_small(d: i64, n: &BigUint) -> i32 {
+    let n_mod_8 = (n % 8u8).to_u64().expect("less than 8");
+    let mut result = 1;
+    let mut a = d.unsigned_abs();
+    if a == 0 {
+        return if n.is_one() { 1 } else { 0 };
+    }
+    if d < 0 && n_mod_8 % 4 == 3 {
+        result = -result;
+    }
+    while a.is_multiple_of(2) {
+        a /= 2;
+        if n_mod_8 == 3 || n_mod_8 == 5 {
+            result = -result;
+        }
+    }
+    if a == 1 {
+        return result;
+    }
+    if a % 4 == 3 && n_mod_8 % 4 == 3 {
+        result = -result;
+    }
+    let n_mod_a = (n % a).to_u64().expect("less than a");
+    result * jacobi_u64(n_mod_a, a)
+}
+
+/// Calculate the Jacobi symbol `(a/n)` for an odd `n`
+fn jacobi_u64(mut a: u64, mut n: u64) -> i32 {
+    let mut result = 1;
+    a %= n;
+    while a != 0 {
+        while a.is_multiple_of(2) {
+            a /= 2;
+            if n % 8 == 3 || n % 8 == 5 {
+                result = -result;
+            }
+        }
+        std::mem::swap(&mut a, &mut n);
+        if a % 4 == 3 && n % 4 == 3 {
+            result = -result;
+        }
+        a %= n;
+    }
+    if n == 1 { result } else { 0 }
+}
+
+/// Check if `a` is a quadratic residue modulo an odd prime `p` by Euler's criterion
+pub(crate) fn is_quadratic_residue(a: &BigUint, p: &BigUint) -> bool {
+    let a = a % p;
+    a.is_zero() || a.modpow(&((p - 1u8) >> 1), p).is_one()
+}
+
+/// Find a quadratic non-residue modulo an odd prime `p`
+fn quadratic_non_residue(p: &BigUint) -> Option<BigUint> {
+    (2..MAX_NON_RESIDUE)
+        .map(BigUint::from)
+        .find(|z| z < p && !is_quadratic_residue(z, p))
+}
+
+/// Square roots modulo an odd prime `p` (Tonelli-Shanks algorithm)
+///
+/// The quadratic non-residue and its power are computed once,
+/// so that each square root costs a single modular exponentiation.
+pub(crate) struct SqrtContext {
+    /// the modulus
+    p: BigUint,
+    /// the exponent `s` of `p - 1 = 2^s q` with an odd `q`
+    s: u64,
+    /// `(q - 1) / 2`
+    half_q: BigUint,
+    /// a quadratic non-residue `z`
+    z: BigUint,
+    /// `z^q`, a primitive `2^s`-th root of unity
+    c: BigUint,
+}
+
+impl SqrtContext {
+    /// Create the context for an odd prime `p`
+    ///
+    /// Returns `None` if no quadratic non-residue is found, which means that `p` is composite.
+    pub(crate) fn new(p: &BigUint) -> Option<Self> {
+        let p_minus_1 = p - 1u8;
+        let s = p_minus_1.trailing_zeros()?;
+        let q = &p_minus_1 >> s;
+        let z = quadratic_non_residue(p)?;
+        let c = z.modpow(&q, p);
+        Some(SqrtContext {
+            p: p.clone(),
+            s,
+            half_q: q >> 1,
+            z,
+            c,
+        })
+    }
+
+    /// The quadratic non-residue
+    pub(crate) fn non_residue(&self) -> &BigUint {
+        &self.z
+    }
+
+    /// Calculate a square root of `a`
+    ///
+    /// ## Returns
+    ///
+    /// - `Some(r)` with `r^2 = a (mod p)`
+    /// - `None` if `a` is not a quadratic residue, or `p` turned out to be composite
+    pub(crate) fn sqrt(&self, a: &BigUint) -> Option<BigUint> {
+        let p = &self.p;
+        let a = a % p;
+        if a.is_zero() {
+            return Some(a);
+        }
+        // r = a^((q + 1) / 2), t = a^q
+        let w = a.modpow(&self.half_q, p);
+        let mut r = &a * &w % p;
+        let mut t = &r * &w % p;
+        let mut m = self.s;
+        let mut c = self.c.clone();
+        while !t.is_one() {
+            // the least i with t^(2^i) = 1
+            let mut i = 0;
+            let mut t2 = t.clone();
+            while !t2.is_one() {
+                t2 = &t2 * &t2 % p;
+                i += 1;
+                if i == m {
+                    return None;
+                }
+            }
+            let mut b = c;
+            for _ in 0..(m - i - 1) {
+                b = &b * &b % p;
+            }
+            m = i;
+            c = &b * &b % p;
+            t = t * &c % p;
+            r = r * b % p;
+        }
+        (&r * &r % p == a).then_some(r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kronecker_small_matches_euler_criterion() {
+        for n in [7u32, 101, 1009, 10007, 65537] {
+            let n_big = BigUint::from(n);
+            for d in -40i64..40 {
+                let a = BigUint::from((d.rem_euclid(i64::from(n))) as u64);
+                let expected = if a.is_zero() {
+                    0
+                } else if is_quadratic_residue(&a, &n_big) {
+                    1
+                } else {
+                    -1
+                };
+                assert_eq!(kronecker_small(d, &n_big), expected, "d = {d}, n = {n}");
+            }
+        }
+    }
+
+    #[test]
+    fn sqrt_with_prime() {
+        // 2^64 - 59 = 5 (mod 8), 65537 = 1 (mod 2^16), 1000003 = 3 (mod 4)
+        for p in [18_446_744_073_709_551_557_u64, 65537, 1_000_003] {
+            let p = BigUint::from(p);
+            let ctx = SqrtContext::new(&p).unwrap();
+            assert!(!is_quadratic_residue(ctx.non_residue(), &p));
+            for a in 2u32..50 {
+                let a = BigUint::from(a);
+                match ctx.sqrt(&a) {
+                    Some(r) => assert_eq!(&r * &r % &p, a),
+                    None => assert!(!is_quadratic_residue(&a, &p)),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,340 @@
+//! Primality proving with a partial factorization of `n - 1`
+//!
+//! Let `n - 1 = F * R`, where `F` is fully factored. If for each prime factor `q` of `F`
+//! there is a witness `a` with `a^(n-1) = 1 (mod n)` and `gcd(a^((n-1)/q) - 1, n) = 1`,
+//! every prime factor of `n` is `1 (mod F)`. Then:
+//!
+//! - Pocklington: `n` is prime if `F > sqrt(n)`
+//! - Brillhart-Lehmer-Selfridge: if `F > cbrt(n)`, write `n = c2 * F^2 + c1 * F + 1` with `0 <= c1 < F`.
+//!   `n` is prime if and only if `c1^2 - 4 * c2` is not a perfect square
+//!
+//! ## References
+//!
+//! - J. Brillhart, D. H. Lehmer and J. L. Selfridge, "New primality criteria and factorizations of 2^m ± 1",
+//!   Math. Comp. 29 (1975)
+//! - R. Crandall and C. Pomerance, "Prime Numbers: A Computational Perspective", 2nd ed., Theorems 4.1.3 - 4.1.5
+
+use crate::factor::factorize;
+use num_bigint::{BigInt, BigUint};
+use num_integer::Integer;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::fmt;
+use yoshi389111_miller_rabin::is_prime_u64;
+
+/// Maximum number of candidates tried as a witness for each factor
+const MAX_WITNESS: u32 = 1000;
+
+/// Certificate of primality based on a partial factorization of `n - 1`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NMinusOneCertificate {
+    /// the certified prime
+    pub n: BigUint,
+    /// the prime factors of the factored part `F` of `n - 1`
+    pub factors: Vec<NMinusOneFactor>,
+}
+
+/// A prime factor of the factored part of `n - 1`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NMinusOneFactor {
+    /// the prime factor `q`
+    pub prime: BigUint,
+    /// the exponent of `q` in `n - 1`
+    pub exponent: u32,
+    /// the witness `a` for `q`
+    pub witness: BigUint,
+    /// certificate of `q`, required if `q` does not fit in 64 bits
+    pub certificate: Option<Box<NMinusOneCertificate>>,
+}
+
+/// Error in proving primality with the factorization of `n - 1`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NMinusOneError {
+    /// `n` is not prime
+    NotPrime,
+    /// the factor does not divide `n - 1`, or its primality cannot be proven
+    InvalidFactor(BigUint),
+    /// the factored part of `n - 1` does not exceed the cube root of `n`
+    InsufficientFactorization,
+    /// no witness was found for the factor, so `n` is most likely composite
+    WitnessNotFound(BigUint),
+}
+
+impl fmt::Display for NMinusOneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NMinusOneError::NotPrime => write!(f, "the number is not prime"),
+            NMinusOneError::InvalidFactor(q) => write!(f, "invalid factor {q}"),
+            NMinusOneError::InsufficientFactorization => {
+                write!(f, "insufficient factorization of n - 1")
+            }
+            NMinusOneError::WitnessNotFound(q) => write!(f, "no witness found for factor {q}"),
+        }
+    }
+}
+
+impl std::error::Error for NMinusOneError {}
+
+impl NMinusOneCertificate {
+    /// Verify the certificate without randomized tests
+    ///
+    /// ## Returns
+    ///
+    /// - `true` if the certificate proves that `self.n` is prime
+    /// - `false` otherwise
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use num_bigint::BigUint;
+    /// use yoshi389111_prime_cert::n_minus_one_certificate;
+    ///
+    /// let n = (BigUint::from(3u8) << 189) + 1u8;
+    /// let cert = n_minus_one_certificate(&n, &[BigUint::from(2u8), BigUint::from(3u8)]).unwrap();
+    /// assert!(cert.verify());
+    /// ```
+    pub fn verify(&self) -> bool {
+        let n = &self.n;
+        if *n == BigUint::from(2u8) {
+            return self.factors.is_empty();
+        }
+        if *n < BigUint::from(3u8) || !n.bit(0) {
+            return false;
+        }
+
+        let n_minus_1 = n - 1u8;
+        let mut f = BigUint::one();
+        for factor in self.factors.iter() {
+            let q = &factor.prime;
+            if factor.exponent == 0 || u64::from(factor.exponent) >= n.bits() {
+                return false;
+            }
+            let q_e = q.pow(factor.exponent);
+            if !(&n_minus_1 % &q_e).is_zero() || !is_proven_prime(factor) {
+                return false;
+            }
+            if !check_witness(n, &n_minus_1, q, &factor.witness) {
+                return false;
+            }
+            f *= q_e;
+        }
+        if !(&n_minus_1 % &f).is_zero() {
+            // the same factor is listed twice
+            return false;
+        }
+
+        if &f * &f > *n {
+            true
+        } else if &f * &f * &f > *n {
+            !bls_square_test(n, &f)
+        } else {
+            false
+        }
+    }
+}
+
+/// Prove the primality of `n` with a partial factorization of `n - 1`
+///
+/// The product `F` of the powers of the given prime factors in `n - 1` must exceed `cbrt(n)`.
+/// Factors of 64 bits or more are proven prime recursively,
+/// which requires factorizing `q - 1` with trial division and Pollard's rho method.
+///
+/// ## Params
+///
+/// - `n`: the number to be proven prime
+/// - `factors`: distinct prime factors of `n - 1`
+///
+/// ## Returns
+///
+/// - `Ok(certificate)` if `n` is proven prime
+/// - `Err(NMinusOneError)` if `n` is not prime, or the proof fails
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use yoshi389111_prime_cert::{NMinusOneError, n_minus_one_certificate};
+///
+/// // 3 * 2^189 + 1
+/// let n = (BigUint::from(3u8) << 189) + 1u8;
+/// let cert = n_minus_one_certificate(&n, &[BigUint::from(2u8), BigUint::from(3u8)]).unwrap();
+/// assert_eq!(cert.n, n);
+///
+/// let n = (BigUint::from(5u8) << 189) + 1u8;
+/// let result = n_minus_one_certificate(&n, &[BigUint::from(2u8), BigUint::from(5u8)]);
+/// assert_eq!(result, Err(NMinusOneError::NotPrime));
+/// ```
+pub fn n_minus_one_certificate(
+    n: &BigUint,
+    factors: &[BigUint],
+) -> Result<NMinusOneCertificate, NMinusOneError> {
+    if *n == BigUint::from(2u8) {
+        return Ok(NMinusOneCertificate {
+            n: n.clone(),
+            factors: Vec::new(),
+        });
+    }
+    if *n < BigUint::from(3u8) || !n.bit(0) {
+        return Err(NMinusOneError::NotPrime);
+    }
+
+    let n_minus_1 = n - 1u8;
+    let mut f = BigUint::one();
+    let mut cert_factors = Vec::new();
+    for q in factors.iter() {
+        if *q <= BigUint::one() || !(&n_minus_1 % q).is_zero() {
+            return Err(NMinusOneError::InvalidFactor(q.clone()));
+        }
+        if cert_factors
+            .iter()
+            .any(|factor: &NMinusOneFactor| factor.prime == *q)
+        {
+            continue;
+        }
+        let mut exponent = 0;
+        let mut m = n_minus_1.clone();
+        while (&m % q).is_zero() {
+            m /= q;
+            exponent += 1;
+        }
+        let certificate = prove_factor(q)?;
+        f *= q.pow(exponent);
+        cert_factors.push(NMinusOneFactor {
+            prime: q.clone(),
+            exponent,
+            witness: BigUint::one(),
+            certificate,
+        });
+    }
+    if &f * &f * &f <= *n {
+        return Err(NMinusOneError::InsufficientFactorization);
+    }
+
+    for factor in cert_factors.iter_mut() {
+        factor.witness = find_witness(n, &n_minus_1, &factor.prime)?;
+    }
+    if &f * &f <= *n && bls_square_test(n, &f) {
+        return Err(NMinusOneError::NotPrime);
+    }
+    Ok(NMinusOneCertificate {
+        n: n.clone(),
+        factors: cert_factors,
+    })
+}
+
+/// Prove the primality of a factor `q`
+///
+/// Factors below `2^64` are proven by the deterministic test for `u64`.
+fn prove_factor(q: &BigUint) -> Result<Option<Box<NMinusOneCertificate>>, NMinusOneError> {
+    if let Some(q64) = q.to_u64() {
+        return if is_prime_u64(q64) {
+            Ok(None)
+        } else {
+            Err(NMinusOneError::InvalidFactor(q.clone()))
+        };
+    }
+    let primes: Vec<BigUint> = factorize(&(q - 1u8)).into_iter().map(|(p, _)| p).collect();
+    n_minus_one_certificate(q, &primes)
+        .map(|cert| Some(Box::new(cert)))
+        .map_err(|_| NMinusOneError::InvalidFactor(q.clone()))
+}
+
+/// Check if the primality of a factor is proven
+fn is_proven_prime(factor: &NMinusOneFactor) -> bool {
+    match (factor.prime.to_u64(), factor.certificate.as_ref()) {
+        (Some(q), _) => is_prime_u64(q),
+        (None, Some(cert)) => cert.n == factor.prime && cert.verify(),
+        (None, None) => false,
+    }
+}
+
+/// Check `a^(n-1) = 1 (mod n)` and `gcd(a^((n-1)/q) - 1, n) = 1`
+fn check_witness(n: &BigUint, n_minus_1: &BigUint, q: &BigUint, a: &BigUint) -> bool {
+    if !a.modpow(n_minus_1, n).is_one() {
+        return false;
+    }
+    let x = a.modpow(&(n_minus_1 / q), n);
+    x > BigUint::one() && (x - 1u8).gcd(n).is_one()
+}
+
+/// Find a witness for the factor `q` of `n - 1`
+fn find_witness(n: &BigUint, n_minus_1: &BigUint, q: &BigUint) -> Result<BigUint, NMinusOneError> {
+    for a in 2..MAX_WITNESS {
+        let a = BigUint::from(a);
+        if a >= *n_minus_1 {
+            break;
+        }
+        if !a.modpow(n_minus_1, n).is_one() {
+            return Err(NMinusOneError::NotPrime);
+        }
+        let x = a.modpow(&(n_minus_1 / q), n);
+        if x.is_one() {
+            continue;
+        }
+        if !(x - 1u8).gcd(n).is_one() {
+            return Err(NMinusOneError::NotPrime);
+        }
+        return Ok(a);
+    }
+    Err(NMinusOneError::WitnessNotFound(q.clone()))
+}
+
+/// Check if `c1^2 - 4 * c2` is a perfect square, where `n = c2 * F^2 + c1 * F + 1`
+fn bls_square_test(n: &BigUint, f: &BigUint) -> bool {
+    let (c2, c1) = ((n - 1u8) / f).div_rem(f);
+    let d = BigInt::from(&c1 * &c1) - BigInt::from(c2 * 4u8);
+    if d.is_negative() {
+        return false;
+    }
+    let root = d.sqrt();
+    &root * &root == d
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pocklington_with_proth_prime() {
+        // 3 * 2^189 + 1, where F = 2^189 exceeds sqrt(n)
+        let n = (BigUint::from(3u8) << 189) + 1u8;
+        let cert = n_minus_one_certificate(&n, &[BigUint::from(2u8)]).unwrap();
+        assert!(cert.verify());
+    }
+
+    #[test]
+    fn bls_with_cube_root_factorization() {
+        // (2^129 + 155) * 2^70 + 1, where F = 2^70 is between cbrt(n) and sqrt(n)
+        let n = (((BigUint::one() << 129) + 155u8) << 70) + 1u8;
+        let cert = n_minus_one_certificate(&n, &[BigUint::from(2u8)]).unwrap();
+        assert!(cert.verify());
+
+        let n = (((BigUint::one() << 129) + 157u8) << 70) + 1u8;
+        assert!(n_minus_one_certificate(&n, &[BigUint::from(2u8)]).is_err());
+    }
+
+    #[test]
+    fn n_minus_one_certificate_with_invalid_factorization() {
+        let n = (((BigUint::one() << 129) + 155u8) << 70) + 1u8;
+        assert_eq!(
+            n_minus_one_certificate(&n, &[BigUint::from(3u8)]),
+            Err(NMinusOneError::InvalidFactor(BigUint::from(3u8)))
+        );
+        let n = (BigUint::from(3u8) << 189) + 1u8;
+        assert_eq!(
+            n_minus_one_certificate(&n, &[BigUint::from(3u8)]),
+            Err(NMinusOneError::InsufficientFactorization)
+        );
+    }
+
+    #[test]
+    fn tampered_certificate_is_rejected() {
+        // a large factor of n - 1 with a nested certificate
+        let q: BigUint = (BigUint::from(3u8) << 189) + 1u8;
+        let n = &q * 94u8 + 1u8;
+        let mut cert = n_minus_one_certificate(&n, &[BigUint::from(2u8), q.clone()]).unwrap();
+        assert!(cert.factors[1].certificate.is_some());
+        assert!(cert.verify());
+        cert.factors[1].certificate = None;
+        assert!(!cert.verify());
+    }
+}
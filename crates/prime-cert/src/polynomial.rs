@@ -0,0 +1,161 @@
+//! Polynomials over `Z/nZ` for a (probable) prime `n`
+//!
+//! A polynomial is a vector of coefficients, where the index is the degree of the term.
+//!
+//! ## References
+//!
+//! - D. G. Cantor and H. Zassenhaus, "A new algorithm for factoring polynomials over finite fields",
+//!   Math. Comp. 36 (1981)
+
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+
+/// Maximum number of shifts tried for splitting a polynomial
+const MAX_SPLIT_TRIALS: u32 = 100;
+
+/// Remove the leading zero coefficients
+fn trim(mut a: Vec<BigUint>) -> Vec<BigUint> {
+    while a.last().is_some_and(Zero::is_zero) {
+        a.pop();
+    }
+    a
+}
+
+/// Calculate `a mod f` for a monic `f`
+///
+/// The coefficients of `a` may exceed `n`, and they are reduced only once,
+/// instead of after each subtraction of a multiple of `f`.
+fn rem(mut a: Vec<BigUint>, f: &[BigUint], n: &BigUint) -> Vec<BigUint> {
+    let d = f.len() - 1;
+    let minus_f: Vec<BigUint> = f[..d].iter().map(|c| (n - c) % n).collect();
+    while a.len() > d {
+        let c = a.pop().expect("not empty") % n;
+        let shift = a.len() - d;
+        for (a_i, minus_f_i) in a[shift..].iter_mut().zip(minus_f.iter()) {
+            *a_i += &c * minus_f_i;
+        }
+    }
+    trim(a.into_iter().map(|c| c % n).collect())
+}
+
+/// Calculate `a / f` for a monic `f` dividing `a`
+fn div_exact(a: &[BigUint], f: &[BigUint], n: &BigUint) -> Vec<BigUint> {
+    let d = f.len() - 1;
+    let mut a = a.to_vec();
+    let mut quotient = vec![BigUint::zero(); a.len() - d];
+    while a.len() > d {
+        let c = a.pop().expect("not empty");
+        let shift = a.len() - d;
+        for (i, f_i) in f[..d].iter().enumerate() {
+            let a_i = &mut a[shift + i];
+            *a_i = (&*a_i + n - &c * f_i % n) % n;
+        }
+        quotient[shift] = c;
+    }
+    quotient
+}
+
+/// Calculate `a^2 mod f` for a monic `f`
+fn sqr_mod(a: &[BigUint], f: &[BigUint], n: &BigUint) -> Vec<BigUint> {
+    if a.is_empty() {
+        return Vec::new();
+    }
+    let mut product = vec![BigUint::zero(); 2 * a.len() - 1];
+    for (i, a_i) in a.iter().enumerate() {
+        for (j, a_j) in a.iter().enumerate().skip(i + 1) {
+            product[i + j] += a_i * a_j;
+        }
+    }
+    for (i, a_i) in a.iter().enumerate() {
+        product[2 * i] = (&product[2 * i] << 1) + a_i * a_i;
+        if 2 * i + 1 < product.len() {
+            product[2 * i + 1] <<= 1;
+        }
+    }
+    rem(product, f, n)
+}
+
+/// Calculate `(x + delta)^e mod f` for a monic `f`
+fn pow_linear_mod(delta: &BigUint, e: &BigUint, f: &[BigUint], n: &BigUint) -> Vec<BigUint> {
+    let mut result = vec![BigUint::one()];
+    for i in (0..e.bits()).rev() {
+        result = sqr_mod(&result, f, n);
+        if e.bit(i) {
+            let mut product = vec![BigUint::zero(); result.len() + 1];
+            for (j, c) in result.iter().enumerate() {
+                product[j + 1] += c;
+                product[j] += c * delta;
+            }
+            result = rem(product, f, n);
+        }
+    }
+    result
+}
+
+/// Make a polynomial monic
+///
+/// Returns `None` if the leading coefficient is not invertible, which means that `n` is composite.
+fn monic(a: Vec<BigUint>, n: &BigUint) -> Option<Vec<BigUint>> {
+    let inv = a.last()?.modinv(n)?;
+    Some(a.into_iter().map(|c| c * &inv % n).collect())
+}
+
+/// Calculate the monic gcd of `a` and `b`
+fn gcd(a: Vec<BigUint>, b: Vec<BigUint>, n: &BigUint) -> Option<Vec<BigUint>> {
+    let (mut a, mut b) = (trim(a), trim(b));
+    while !b.is_empty() {
+        let b_monic = monic(b, n)?;
+        let r = rem(a, &b_monic, n);
+        a = b_monic;
+        b = r;
+    }
+    monic(a, n)
+}
+
+/// Find a root of a monic polynomial that splits into distinct linear factors
+///
+/// ## Returns
+///
+/// - `Some(root)` if a root is found
+/// - `None` if the polynomial could not be split, e.g. it does not split or `n` is composite
+pub(crate) fn find_root(f: &[BigUint], n: &BigUint) -> Option<BigUint> {
+    let mut f = f.to_vec();
+    let half = (n - 1u8) >> 1;
+    'split: while f.len() > 2 {
+        // gcd((x + delta)^((n - 1) / 2) - 1, f) separates the roots by the quadratic character
+        for delta in 0..MAX_SPLIT_TRIALS {
+            let mut g = pow_linear_mod(&(BigUint::from(delta) % n), &half, &f, n);
+            if g.is_empty() {
+                g.push(BigUint::zero());
+            }
+            g[0] = (&g[0] + n - 1u8) % n;
+            let g = gcd(f.clone(), g, n)?;
+            if g.len() > 1 && g.len() < f.len() {
+                // continue with the smaller factor
+                let h = div_exact(&f, &g, n);
+                f = if g.len() <= h.len() { g } else { h };
+                continue 'split;
+            }
+        }
+        return None;
+    }
+    let f = monic(f, n)?;
+    Some((n - &f[0]) % n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_root_of_split_polynomial() {
+        let n = BigUint::from(1_000_003_u32);
+        // (x - 2)(x - 3)(x - 5)(x - 7) = x^4 - 17x^3 + 101x^2 - 247x + 210
+        let f: Vec<BigUint> = [210, -247, 101, -17, 1]
+            .iter()
+            .map(|&c: &i64| BigUint::from(c.rem_euclid(1_000_003) as u64))
+            .collect();
+        let root = find_root(&f, &n).unwrap();
+        assert!([2u8, 3, 5, 7].iter().any(|&r| root == BigUint::from(r)));
+    }
+}
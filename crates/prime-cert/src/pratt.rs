@@ -0,0 +1,284 @@
+//! Pratt primality certificates
+//!
+//! A Pratt certificate of a prime `p` is an element `a` of order `p - 1` modulo `p`
+//! with the prime factorization of `p - 1`, and the certificates of the factors.
+//! By Lucas' theorem, `p` is prime if `a^(p-1) = 1 (mod p)`
+//! and `a^((p-1)/q) != 1 (mod p)` for every prime factor `q` of `p - 1`.
+//!
+//! ## Text format
+//!
+//! One line per prime, where each factor appears on an earlier line than the primes that use it,
+//! and the last line is the certified prime:
+//!
+//! ```text
+//! 2
+//! 3 2 2^1
+//! 7 3 2^1 3^1
+//! ```
+//!
+//! Each line has the prime, the witness `a` and the factors of `p - 1` as `q^e`.
+//! The line of the prime two has no witness and no factors.
+//!
+//! ## References
+//!
+//! - V. R. Pratt, "Every prime has a succinct certificate", SIAM J. Comput. 4 (1975)
+
+use crate::ParseCertificateError;
+use crate::factor::factorize;
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+use yoshi389111_miller_rabin::is_probable_prime;
+
+/// Number of Miller-Rabin rounds before building a certificate
+const ROUNDS: usize = 40;
+
+/// Pratt primality certificate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrattCertificate {
+    /// the certified prime
+    pub prime: BigUint,
+    /// an element of order `prime - 1` modulo `prime` (one for the prime two)
+    pub witness: BigUint,
+    /// certificates of the prime factors of `prime - 1`, with their exponents
+    pub factors: Vec<(PrattCertificate, u32)>,
+}
+
+impl PrattCertificate {
+    /// Verify the certificate without randomized tests
+    ///
+    /// ## Returns
+    ///
+    /// - `true` if the certificate proves that `self.prime` is prime
+    /// - `false` otherwise
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use num_bigint::BigUint;
+    /// use yoshi389111_prime_cert::pratt_certificate;
+    ///
+    /// let cert = pratt_certificate(&BigUint::from(389_111_u64)).unwrap();
+    /// assert!(cert.verify());
+    /// ```
+    pub fn verify(&self) -> bool {
+        let p = &self.prime;
+        if *p == BigUint::from(2u8) {
+            return self.factors.is_empty();
+        }
+        if *p < BigUint::from(3u8) || !p.bit(0) {
+            return false;
+        }
+
+        let p_minus_1 = p - 1u8;
+        // the exponents are bounded before computing the product
+        if self
+            .factors
+            .iter()
+            .any(|(_, e)| *e == 0 || u64::from(*e) >= p.bits())
+        {
+            return false;
+        }
+        let product = self
+            .factors
+            .iter()
+            .fold(BigUint::one(), |acc, (q, e)| acc * q.prime.pow(*e));
+        if product != p_minus_1 {
+            return false;
+        }
+        if !self.witness.modpow(&p_minus_1, p).is_one() {
+            return false;
+        }
+        self.factors
+            .iter()
+            .all(|(q, _)| !self.witness.modpow(&(&p_minus_1 / &q.prime), p).is_one() && q.verify())
+    }
+}
+
+/// Build a Pratt primality certificate
+///
+/// `p - 1` is factorized recursively with trial division and Pollard's rho method,
+/// so this is practical for primes whose `p - 1` has no two large prime factors,
+/// e.g. up to about 128 bits in general.
+///
+/// ## Params
+///
+/// - `p`: the number to be certified
+///
+/// ## Returns
+///
+/// - `Some(certificate)` if `p` is prime
+/// - `None` if `p` is not prime
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use yoshi389111_prime_cert::pratt_certificate;
+///
+/// let cert = pratt_certificate(&BigUint::from(389_111_u64)).unwrap();
+/// assert_eq!(cert.prime, BigUint::from(389_111_u64));
+/// assert!(pratt_certificate(&BigUint::from(389_111_u64 * 3)).is_none());
+/// ```
+pub fn pratt_certificate(p: &BigUint) -> Option<PrattCertificate> {
+    if *p == BigUint::from(2u8) {
+        return Some(PrattCertificate {
+            prime: p.clone(),
+            witness: BigUint::one(),
+            factors: Vec::new(),
+        });
+    }
+    if !is_probable_prime(p, ROUNDS) {
+        return None;
+    }
+
+    let p_minus_1 = p - 1u8;
+    let factors = factorize(&p_minus_1)
+        .into_iter()
+        .map(|(q, e)| pratt_certificate(&q).map(|cert| (cert, e)))
+        .collect::<Option<Vec<_>>>()?;
+
+    let mut a = BigUint::from(2u8);
+    while &a < p {
+        if !a.modpow(&p_minus_1, p).is_one() {
+            return None;
+        }
+        if factors
+            .iter()
+            .all(|(q, _)| !a.modpow(&(&p_minus_1 / &q.prime), p).is_one())
+        {
+            return Some(PrattCertificate {
+                prime: p.clone(),
+                witness: a,
+                factors,
+            });
+        }
+        a += 1u8;
+    }
+    None
+}
+
+impl fmt::Display for PrattCertificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write_lines(
+            cert: &PrattCertificate,
+            seen: &mut BTreeSet<BigUint>,
+            f: &mut fmt::Formatter<'_>,
+        ) -> fmt::Result {
+            if !seen.insert(cert.prime.clone()) {
+                return Ok(());
+            }
+            for (q, _) in cert.factors.iter() {
+                write_lines(q, seen, f)?;
+            }
+            if cert.factors.is_empty() {
+                return writeln!(f, "{}", cert.prime);
+            }
+            write!(f, "{} {}", cert.prime, cert.witness)?;
+            for (q, e) in cert.factors.iter() {
+                write!(f, " {}^{}", q.prime, e)?;
+            }
+            writeln!(f)
+        }
+        write_lines(self, &mut BTreeSet::new(), f)
+    }
+}
+
+impl FromStr for PrattCertificate {
+    type Err = ParseCertificateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut certs: BTreeMap<BigUint, PrattCertificate> = BTreeMap::new();
+        let mut last = None;
+        for (index, line) in s.lines().enumerate() {
+            let line_no = index + 1;
+            let mut tokens = line.split_whitespace();
+            let Some(prime) = tokens.next() else {
+                continue;
+            };
+            let prime = parse_number(prime, line_no)?;
+            let witness = match tokens.next() {
+                Some(token) => parse_number(token, line_no)?,
+                None => BigUint::one(),
+            };
+            let factors = tokens
+                .map(|token| {
+                    let (q, e) = token
+                        .split_once('^')
+                        .ok_or(ParseCertificateError::InvalidLine(line_no))?;
+                    let q = parse_number(q, line_no)?;
+                    let e = e
+                        .parse::<u32>()
+                        .map_err(|_| ParseCertificateError::InvalidLine(line_no))?;
+                    let cert = certs
+                        .get(&q)
+                        .ok_or(ParseCertificateError::UnknownFactor(line_no))?;
+                    Ok((cert.clone(), e))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let cert = PrattCertificate {
+                prime: prime.clone(),
+                witness,
+                factors,
+            };
+            certs.insert(prime, cert.clone());
+            last = Some(cert);
+        }
+        last.ok_or(ParseCertificateError::Empty)
+    }
+}
+
+/// Parse a decimal number in a line of a certificate
+fn parse_number(token: &str, line_no: usize) -> Result<BigUint, ParseCertificateError> {
+    BigUint::parse_bytes(token.as_bytes(), 10)
+        .filter(|n| !n.is_zero())
+        .ok_or(ParseCertificateError::InvalidLine(line_no))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pratt_certificate_verifies() {
+        for p in [2u64, 3, 5, 389_111, 1_000_000_007, (1 << 61) - 1] {
+            let cert = pratt_certificate(&BigUint::from(p)).unwrap();
+            assert!(cert.verify(), "p = {p}");
+        }
+        let p = (BigUint::from(1u8) << 127) - 1u8;
+        assert!(pratt_certificate(&p).unwrap().verify());
+    }
+
+    #[test]
+    fn pratt_certificate_with_composite() {
+        assert!(pratt_certificate(&BigUint::from(0u8)).is_none());
+        assert!(pratt_certificate(&BigUint::from(1u8)).is_none());
+        assert!(pratt_certificate(&BigUint::from(561u32)).is_none());
+    }
+
+    #[test]
+    fn tampered_certificate_is_rejected() {
+        let mut cert = pratt_certificate(&BigUint::from(1_000_000_007_u32)).unwrap();
+        cert.witness = BigUint::one();
+        assert!(!cert.verify());
+
+        // 561 - 1 = 2^4 * 5 * 7, and 561 is a Carmichael number
+        let text = "2\n3 2 2^1\n5 2 2^2\n7 3 2^1 3^1\n561 2 2^4 5^1 7^1\n";
+        assert!(!text.parse::<PrattCertificate>().unwrap().verify());
+    }
+
+    #[test]
+    fn text_format_round_trip() {
+        let cert = pratt_certificate(&BigUint::from(7u8)).unwrap();
+        assert_eq!(cert.to_string(), "2\n3 2 2^1\n7 3 2^1 3^1\n");
+        let p = (BigUint::from(1u8) << 89) - 1u8;
+        let cert = pratt_certificate(&p).unwrap();
+        assert_eq!(cert.to_string().parse::<PrattCertificate>(), Ok(cert));
+        assert_eq!(
+            "3 2 2^1".parse::<PrattCertificate>(),
+            Err(ParseCertificateError::UnknownFactor(1))
+        );
+    }
+}
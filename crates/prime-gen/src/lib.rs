@@ -0,0 +1,35 @@
+//! Generation of random prime numbers
+//!
+//! ## Features
+//!
+//! - Generation of RSA primes `p` and `q` as specified in FIPS 186-5 Appendix A.1.3
+//! - Generation of safe primes and Sophie Germain primes with a combined sieve
+//! - Generation of uniformly chosen random primes of a given bit length or in a range
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use num_bigint::BigUint;
+//! use yoshi389111_prime_gen::generate_rsa_primes;
+//!
+//! let e = BigUint::from(65_537_u32);
+//! let (p, q) = generate_rsa_primes(2048, &e).unwrap();
+//! ```
+//!
+//! ## Reference
+//!
+//! - <https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.186-5.pdf>
+//!   - Appendix A.1.3 Generation of Random Primes that are Probably Prime
+
+mod random;
+mod rsa;
+mod safe_prime;
+pub use crate::random::{
+    TopBits, random_prime, random_prime_in_range, random_prime_in_range_with_rng,
+    random_prime_with_rng,
+};
+pub use crate::rsa::{RsaPrimeError, generate_rsa_primes, generate_rsa_primes_with_rng};
+pub use crate::safe_prime::{
+    generate_safe_prime, generate_safe_prime_with_rng, generate_sophie_germain_prime,
+    generate_sophie_germain_prime_with_rng,
+};
@@ -0,0 +1,253 @@
+//! Random prime generation
+//!
+//! Every candidate is drawn independently and uniformly, and rejected unless it is a probable prime.
+//! Unlike searching for the next prime from a random starting point,
+//! which favors primes that follow large prime gaps,
+//! this selects each prime in the range with (almost) the same probability.
+
+use num_bigint::{BigUint, RandBigInt};
+use yoshi389111_miller_rabin::is_probable_prime_with_rng;
+
+/// Number of most significant bits forced to one in a random prime
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopBits {
+    /// Only the most significant bit is set, so the prime has exactly the requested bit length
+    One,
+    /// The two most significant bits are set,
+    /// so the product of two such primes has exactly twice the bit length
+    Two,
+}
+
+/// Maximum width of a range for which all primes are enumerated
+const ENUMERATION_THRESHOLD: u32 = 1 << 16;
+
+/// Number of random trials per bit before falling back to a sequential scan
+const MAX_TRIALS_PER_BIT: u64 = 32;
+
+/// Generate a random odd prime of the specified bit length
+///
+/// Candidates are odd numbers with the top bits set,
+/// drawn uniformly and independently until a probable prime is found.
+///
+/// ## Params
+///
+/// - `bits`: bit length of the prime (at least 2)
+/// - `top_bits`: number of most significant bits forced to one
+/// - `iter`: number of Miller-Rabin iterations
+/// - `rng`: random number generator
+///
+/// ## Returns
+///
+/// - a probable prime with exactly `bits` bits
+///
+/// ## Panics
+///
+/// - if `bits` is less than 2
+///
+/// ## Example
+///
+/// ```rust
+/// use rand::rngs::OsRng;
+/// use yoshi389111_prime_gen::{TopBits, random_prime_with_rng};
+///
+/// let mut rng = OsRng;
+/// let p = random_prime_with_rng(512, TopBits::Two, 40, &mut rng);
+/// assert_eq!(p.bits(), 512);
+/// assert!(p.bit(510));
+/// ```
+pub fn random_prime_with_rng<R: rand::Rng + ?Sized>(
+    bits: u64,
+    top_bits: TopBits,
+    iter: usize,
+    rng: &mut R,
+) -> BigUint {
+    assert!(bits >= 2, "there is no odd prime with less than 2 bits");
+    loop {
+        let mut candidate = rng.gen_biguint(bits);
+        candidate.set_bit(bits - 1, true);
+        if top_bits == TopBits::Two {
+            candidate.set_bit(bits - 2, true);
+        }
+        candidate.set_bit(0, true);
+        if is_probable_prime_with_rng(&candidate, iter, rng) {
+            return candidate;
+        }
+    }
+}
+
+/// Generate a random odd prime of the specified bit length with OS random number generator
+///
+/// ## Params
+///
+/// - `bits`: bit length of the prime (at least 2)
+/// - `top_bits`: number of most significant bits forced to one
+/// - `iter`: number of Miller-Rabin iterations
+///
+/// ## Returns
+///
+/// - a probable prime with exactly `bits` bits
+///
+/// ## Panics
+///
+/// - if `bits` is less than 2
+///
+/// ## Example
+///
+/// ```rust
+/// use yoshi389111_prime_gen::{TopBits, random_prime};
+///
+/// let p = random_prime(256, TopBits::One, 40);
+/// assert_eq!(p.bits(), 256);
+/// ```
+pub fn random_prime(bits: u64, top_bits: TopBits, iter: usize) -> BigUint {
+    random_prime_with_rng(bits, top_bits, iter, &mut rand::rngs::OsRng)
+}
+
+/// Generate a random prime in the range `[lo, hi)`
+///
+/// Candidates are drawn uniformly from the range until a probable prime is found.
+/// For a narrow range, all primes in the range are enumerated and one of them is chosen uniformly.
+/// If a wide range seems to contain very few primes, the range is scanned sequentially
+/// from a random point, so that the function terminates even if the range contains no prime.
+///
+/// ## Params
+///
+/// - `lo`: lower bound (inclusive)
+/// - `hi`: upper bound (exclusive)
+/// - `iter`: number of Miller-Rabin iterations
+/// - `rng`: random number generator
+///
+/// ## Returns
+///
+/// - `Some(prime)` if a probable prime is found
+/// - `None` if the range is empty or contains no prime
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use rand::rngs::OsRng;
+/// use yoshi389111_prime_gen::random_prime_in_range_with_rng;
+///
+/// let lo = BigUint::from(1u8) << 100;
+/// let hi = BigUint::from(1u8) << 101;
+/// let mut rng = OsRng;
+/// let p = random_prime_in_range_with_rng(&lo, &hi, 40, &mut rng).unwrap();
+/// assert!(lo <= p && p < hi);
+/// ```
+pub fn random_prime_in_range_with_rng<R: rand::Rng + ?Sized>(
+    lo: &BigUint,
+    hi: &BigUint,
+    iter: usize,
+    rng: &mut R,
+) -> Option<BigUint> {
+    if lo >= hi {
+        return None;
+    }
+    if hi - lo <= BigUint::from(ENUMERATION_THRESHOLD) {
+        let mut primes = Vec::new();
+        let mut n = lo.clone();
+        while &n < hi {
+            if is_probable_prime_with_rng(&n, iter, rng) {
+                primes.push(n.clone());
+            }
+            n += 1u8;
+        }
+        if primes.is_empty() {
+            return None;
+        }
+        let index = rng.gen_range(0..primes.len());
+        return Some(primes.swap_remove(index));
+    }
+    // the expected number of trials is about `ln(hi)`
+    for _ in 0..MAX_TRIALS_PER_BIT * hi.bits() {
+        let candidate = rng.gen_biguint_range(lo, hi);
+        if is_probable_prime_with_rng(&candidate, iter, rng) {
+            return Some(candidate);
+        }
+    }
+    // fall back to a sequential scan from a random point, which always terminates
+    let start = rng.gen_biguint_range(lo, hi);
+    let mut n = start.clone();
+    loop {
+        if is_probable_prime_with_rng(&n, iter, rng) {
+            return Some(n);
+        }
+        n += 1u8;
+        if &n == hi {
+            n = lo.clone();
+        }
+        if n == start {
+            return None;
+        }
+    }
+}
+
+/// Generate a random prime in the range `[lo, hi)` with OS random number generator
+///
+/// ## Params
+///
+/// - `lo`: lower bound (inclusive)
+/// - `hi`: upper bound (exclusive)
+/// - `iter`: number of Miller-Rabin iterations
+///
+/// ## Returns
+///
+/// - `Some(prime)` if a probable prime is found
+/// - `None` if the range is empty or contains no prime
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use yoshi389111_prime_gen::random_prime_in_range;
+///
+/// let p = random_prime_in_range(&BigUint::from(24u8), &BigUint::from(29u8), 40);
+/// assert_eq!(p, None);
+/// ```
+pub fn random_prime_in_range(lo: &BigUint, hi: &BigUint, iter: usize) -> Option<BigUint> {
+    random_prime_in_range_with_rng(lo, hi, iter, &mut rand::rngs::OsRng)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use yoshi389111_miller_rabin::is_bpsw_prime;
+
+    #[test]
+    fn random_prime_has_requested_bits() {
+        let mut rng = StdRng::seed_from_u64(389_111);
+        for bits in [2, 3, 8, 64, 256] {
+            let p = random_prime_with_rng(bits, TopBits::One, 20, &mut rng);
+            assert_eq!(p.bits(), bits);
+            assert!(is_bpsw_prime(&p));
+
+            let p = random_prime_with_rng(bits, TopBits::Two, 20, &mut rng);
+            assert_eq!(p.bits(), bits);
+            assert!(p.bit(bits - 2));
+            assert!(is_bpsw_prime(&p));
+        }
+    }
+
+    #[test]
+    fn random_prime_in_range_stays_in_range() {
+        let mut rng = StdRng::seed_from_u64(389_111);
+        let lo = BigUint::from(2u8);
+        let hi = BigUint::from(3u8);
+        assert_eq!(
+            random_prime_in_range_with_rng(&lo, &hi, 20, &mut rng),
+            Some(BigUint::from(2u8))
+        );
+        assert_eq!(random_prime_in_range_with_rng(&hi, &lo, 20, &mut rng), None);
+
+        let lo = BigUint::from(1u8) << 80;
+        let hi = &lo + (BigUint::from(1u8) << 20);
+        for _ in 0..10 {
+            let p = random_prime_in_range_with_rng(&lo, &hi, 20, &mut rng).unwrap();
+            assert!(lo <= p && p < hi);
+            assert!(is_bpsw_prime(&p));
+        }
+    }
+}
@@ -0,0 +1,239 @@
+//! RSA prime generation
+//!
+//! ## Reference
+//!
+//! - <https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.186-5.pdf>
+//!   - Appendix A.1.3 Generation of Random Primes that are Probably Prime
+//!   - Appendix B.3, Table B.1 Minimum number of rounds of M-R testing
+//!     when generating primes for use in RSA Digital Signatures
+
+use num_bigint::{BigUint, RandBigInt};
+use num_integer::Integer;
+use std::fmt;
+use yoshi389111_miller_rabin::{is_probable_prime_with_rng, min_rounds};
+
+/// Minimum modulus length allowed by FIPS 186-5
+const MIN_NLEN: u64 = 2048;
+
+/// Error returned when RSA primes cannot be generated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RsaPrimeError {
+    /// `nlen` is odd or less than 2048
+    InvalidModulusLength,
+    /// `e` is not an odd number with `2^16 < e < 2^256`
+    InvalidPublicExponent,
+    /// no prime was found within the number of iterations allowed by the standard
+    IterationLimitExceeded,
+}
+
+impl fmt::Display for RsaPrimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RsaPrimeError::InvalidModulusLength => write!(f, "invalid modulus length"),
+            RsaPrimeError::InvalidPublicExponent => write!(f, "invalid public exponent"),
+            RsaPrimeError::IterationLimitExceeded => write!(f, "iteration limit exceeded"),
+        }
+    }
+}
+
+impl std::error::Error for RsaPrimeError {}
+
+/// Generate a pair of RSA primes `p` and `q` (FIPS 186-5 Appendix A.1.3)
+///
+/// Both primes are `nlen / 2` bits long and satisfy the following conditions:
+///
+/// - `p, q >= sqrt(2) * 2^(nlen/2 - 1)`
+/// - `gcd(p - 1, e) = gcd(q - 1, e) = 1`
+/// - `|p - q| > 2^(nlen/2 - 100)`
+///
+/// The primality is tested by the Miller-Rabin test with the number of rounds of Table B.1.
+///
+/// ## Params
+///
+/// - `nlen`: the intended length of the modulus `n = p * q` in bits (even, at least 2048)
+/// - `e`: the public exponent (odd, `2^16 < e < 2^256`)
+/// - `rng`: random number generator
+///
+/// ## Returns
+///
+/// - `Ok((p, q))` if the primes are generated
+/// - `Err(RsaPrimeError)` if the parameters are invalid or the generation fails
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use rand::rngs::OsRng;
+/// use yoshi389111_prime_gen::generate_rsa_primes_with_rng;
+///
+/// let e = BigUint::from(65_537_u32);
+/// let mut rng = OsRng;
+/// let (p, q) = generate_rsa_primes_with_rng(2048, &e, &mut rng).unwrap();
+/// assert_eq!(p.bits(), 1024);
+/// assert_eq!(q.bits(), 1024);
+/// ```
+pub fn generate_rsa_primes_with_rng<R: rand::Rng + ?Sized>(
+    nlen: u64,
+    e: &BigUint,
+    rng: &mut R,
+) -> Result<(BigUint, BigUint), RsaPrimeError> {
+    // step 1.
+    if nlen < MIN_NLEN || nlen.is_odd() {
+        return Err(RsaPrimeError::InvalidModulusLength);
+    }
+    // step 2.
+    if e.bits() <= 16 || e.bits() > 256 || e.is_even() {
+        return Err(RsaPrimeError::InvalidPublicExponent);
+    }
+    // step 3.
+    let half = nlen / 2;
+    let rounds = min_rounds(half, security_strength(nlen));
+
+    // step 4.
+    let p = generate_prime(half, e, rounds, 5 * half, rng, |_| true)?;
+    // step 5.
+    let min_diff = BigUint::from(1u8) << (half - 100);
+    let q = generate_prime(half, e, rounds, 10 * half, rng, |q| {
+        // step 5.4
+        let diff = if &p > q { &p - q } else { q - &p };
+        diff > min_diff
+    })?;
+    // step 6.
+    Ok((p, q))
+}
+
+/// Generate a pair of RSA primes `p` and `q` with OS random number generator
+///
+/// ## Params
+///
+/// - `nlen`: the intended length of the modulus `n = p * q` in bits (even, at least 2048)
+/// - `e`: the public exponent (odd, `2^16 < e < 2^256`)
+///
+/// ## Returns
+///
+/// - `Ok((p, q))` if the primes are generated
+/// - `Err(RsaPrimeError)` if the parameters are invalid or the generation fails
+///
+/// ## Example
+///
+/// ```rust
+/// use num_bigint::BigUint;
+/// use yoshi389111_prime_gen::generate_rsa_primes;
+///
+/// let e = BigUint::from(65_537_u32);
+/// let (p, q) = generate_rsa_primes(2048, &e).unwrap();
+/// assert_ne!(p, q);
+/// ```
+pub fn generate_rsa_primes(nlen: u64, e: &BigUint) -> Result<(BigUint, BigUint), RsaPrimeError> {
+    generate_rsa_primes_with_rng(nlen, e, &mut rand::rngs::OsRng)
+}
+
+/// Security strength for the modulus length, which determines the target error probability
+fn security_strength(nlen: u64) -> u32 {
+    if nlen < 3072 {
+        112
+    } else if nlen < 4096 {
+        128
+    } else {
+        144
+    }
+}
+
+/// Steps 4.1 - 4.7 (or 5.1 - 5.8): generate a `bits`-bit probable prime
+///
+/// `accept` is an additional condition checked before the primality test (step 5.4).
+/// A candidate rejected by the lower bound or by `accept` is replaced without counting an iteration,
+/// so only the candidates that reach the primality test count against `max_iter`.
+fn generate_prime<R, F>(
+    bits: u64,
+    e: &BigUint,
+    rounds: usize,
+    max_iter: u64,
+    rng: &mut R,
+    accept: F,
+) -> Result<BigUint, RsaPrimeError>
+where
+    R: rand::Rng + ?Sized,
+    F: Fn(&BigUint) -> bool,
+{
+    // `sqrt(2) * 2^(bits - 1) <= p` is equivalent to `2^(2 * bits - 1) <= p^2`
+    let lower_bound_squared = BigUint::from(1u8) << (2 * bits - 1);
+    // step 4.1
+    let mut i = 0;
+    while i < max_iter {
+        // step 4.2 - 4.3
+        let p = rng.gen_biguint(bits) | BigUint::from(1u8);
+        // step 4.4 (and step 5.4 - 5.5), back to step 4.2 without counting
+        if &p * &p < lower_bound_squared || !accept(&p) {
+            continue;
+        }
+        // step 4.5
+        if (&p - 1u8).gcd(e) == BigUint::from(1u8) && is_probable_prime_with_rng(&p, rounds, rng) {
+            return Ok(p);
+        }
+        // step 4.6
+        i += 1;
+    }
+    // step 4.7
+    Err(RsaPrimeError::IterationLimitExceeded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn generate_rsa_primes_satisfies_constraints() {
+        let e = BigUint::from(65_537_u32);
+        let mut rng = StdRng::seed_from_u64(389_111);
+        let (p, q) = generate_rsa_primes_with_rng(2048, &e, &mut rng).unwrap();
+        let lower_bound_squared = BigUint::from(1u8) << 2047;
+        let min_diff = BigUint::from(1u8) << 924;
+        for x in [&p, &q] {
+            assert_eq!(x.bits(), 1024);
+            assert!(x * x >= lower_bound_squared);
+            assert_eq!((x - 1u8).gcd(&e), BigUint::from(1u8));
+            assert!(yoshi389111_miller_rabin::is_bpsw_prime(x));
+        }
+        let diff = if p > q { &p - &q } else { &q - &p };
+        assert!(diff > min_diff);
+    }
+
+    #[test]
+    fn rejected_candidates_do_not_count_as_iterations() {
+        let e = BigUint::from(65_537_u32);
+        let mut rng = StdRng::seed_from_u64(389_111);
+        let calls = std::cell::Cell::new(0);
+        let _ = generate_prime(64, &e, 10, 1, &mut rng, |_| {
+            calls.set(calls.get() + 1);
+            calls.get() > 50
+        });
+        // the first 50 candidates are rejected before the single primality test allowed
+        assert!(calls.get() > 50);
+    }
+
+    #[test]
+    fn generate_rsa_primes_rejects_invalid_parameters() {
+        let e = BigUint::from(65_537_u32);
+        assert_eq!(
+            generate_rsa_primes(1024, &e),
+            Err(RsaPrimeError::InvalidModulusLength)
+        );
+        assert_eq!(
+            generate_rsa_primes(2049, &e),
+            Err(RsaPrimeError::InvalidModulusLength)
+        );
+        for e in [
+            BigUint::from(3u8),
+            BigUint::from(65_536_u32),
+            BigUint::from(1u8) << 256,
+        ] {
+            assert_eq!(
+                generate_rsa_primes(2048, &e),
+                Err(RsaPrimeError::InvalidPublicExponent)
+            );
+        }
+    }
+}
@@ -0,0 +1,228 @@
+//! Safe prime and Sophie Germain prime generation
+//!
+//! A prime `p` is a safe prime if `q = (p - 1) / 2` is also a prime,
+//! and `q` is then called a Sophie Germain prime.
+//!
+//! Candidates are screened by a combined sieve, which rejects `q` if either `q` or `2q + 1`
+//! has a small prime factor, before running the Miller-Rabin test on both numbers.
+
+use num_bigint::{BigUint, RandBigInt};
+use num_traits::ToPrimitive;
+use once_cell::sync::Lazy;
+use yoshi389111_miller_rabin::is_probable_prime_with_rng;
+
+/// Maximum prime number for the sieve
+const MAX_SIEVE_PRIME: u32 = 10_000;
+
+/// A list of odd small prime numbers for the sieve
+static SIEVE_PRIMES: Lazy<Vec<u32>> = Lazy::new(|| {
+    yoshi389111_prime_iter::new::<u32>()
+        .skip(1)
+        .take_while(|p| *p <= MAX_SIEVE_PRIME)
+        .collect()
+});
+
+/// Generate a random safe prime `p = 2q + 1` of the specified bit length
+///
+/// ## Params
+///
+/// - `bits`: bit length of the safe prime `p` (at least 3)
+/// - `iter`: number of Miller-Rabin iterations for each of `p` and `q`
+/// - `rng`: random number generator
+///
+/// ## Returns
+///
+/// - a safe prime with exactly `bits` bits
+///
+/// ## Panics
+///
+/// - if `bits` is less than 3
+///
+/// ## Example
+///
+/// ```rust
+/// use rand::rngs::OsRng;
+/// use yoshi389111_prime_gen::generate_safe_prime_with_rng;
+///
+/// let mut rng = OsRng;
+/// let p = generate_safe_prime_with_rng(256, 40, &mut rng);
+/// assert_eq!(p.bits(), 256);
+/// ```
+pub fn generate_safe_prime_with_rng<R: rand::Rng + ?Sized>(
+    bits: u64,
+    iter: usize,
+    rng: &mut R,
+) -> BigUint {
+    assert!(bits >= 3, "there is no safe prime with less than 3 bits");
+    let (_, p) = search(bits - 1, iter, rng);
+    p
+}
+
+/// Generate a random safe prime `p = 2q + 1` of the specified bit length with OS random number generator
+///
+/// ## Params
+///
+/// - `bits`: bit length of the safe prime `p` (at least 3)
+/// - `iter`: number of Miller-Rabin iterations for each of `p` and `q`
+///
+/// ## Returns
+///
+/// - a safe prime with exactly `bits` bits
+///
+/// ## Panics
+///
+/// - if `bits` is less than 3
+///
+/// ## Example
+///
+/// ```rust
+/// use yoshi389111_prime_gen::generate_safe_prime;
+///
+/// let p = generate_safe_prime(128, 40);
+/// assert_eq!(p.bits(), 128);
+/// ```
+pub fn generate_safe_prime(bits: u64, iter: usize) -> BigUint {
+    generate_safe_prime_with_rng(bits, iter, &mut rand::rngs::OsRng)
+}
+
+/// Generate a random Sophie Germain prime `q` (`2q + 1` is also a prime) of the specified bit length
+///
+/// ## Params
+///
+/// - `bits`: bit length of the Sophie Germain prime `q` (at least 2)
+/// - `iter`: number of Miller-Rabin iterations for each of `q` and `2q + 1`
+/// - `rng`: random number generator
+///
+/// ## Returns
+///
+/// - a Sophie Germain prime with exactly `bits` bits
+///
+/// ## Panics
+///
+/// - if `bits` is less than 2
+///
+/// ## Example
+///
+/// ```rust
+/// use rand::rngs::OsRng;
+/// use yoshi389111_prime_gen::generate_sophie_germain_prime_with_rng;
+///
+/// let mut rng = OsRng;
+/// let q = generate_sophie_germain_prime_with_rng(256, 40, &mut rng);
+/// assert_eq!(q.bits(), 256);
+/// ```
+pub fn generate_sophie_germain_prime_with_rng<R: rand::Rng + ?Sized>(
+    bits: u64,
+    iter: usize,
+    rng: &mut R,
+) -> BigUint {
+    assert!(
+        bits >= 2,
+        "there is no odd Sophie Germain prime with less than 2 bits"
+    );
+    let (q, _) = search(bits, iter, rng);
+    q
+}
+
+/// Generate a random Sophie Germain prime `q` of the specified bit length with OS random number generator
+///
+/// ## Params
+///
+/// - `bits`: bit length of the Sophie Germain prime `q` (at least 2)
+/// - `iter`: number of Miller-Rabin iterations for each of `q` and `2q + 1`
+///
+/// ## Returns
+///
+/// - a Sophie Germain prime with exactly `bits` bits
+///
+/// ## Panics
+///
+/// - if `bits` is less than 2
+///
+/// ## Example
+///
+/// ```rust
+/// use yoshi389111_prime_gen::generate_sophie_germain_prime;
+///
+/// let q = generate_sophie_germain_prime(128, 40);
+/// assert_eq!(q.bits(), 128);
+/// ```
+pub fn generate_sophie_germain_prime(bits: u64, iter: usize) -> BigUint {
+    generate_sophie_germain_prime_with_rng(bits, iter, &mut rand::rngs::OsRng)
+}
+
+/// Search for an odd prime `q` of `q_bits` bits such that `p = 2q + 1` is also a prime
+///
+/// Starting from a random odd `q`, the candidates `q, q + 2, q + 4, ...` are screened by the sieve.
+/// When `q` exceeds `q_bits` bits, the search restarts from a new random number.
+fn search<R: rand::Rng + ?Sized>(q_bits: u64, iter: usize, rng: &mut R) -> (BigUint, BigUint) {
+    let top_bit = BigUint::from(1u8) << (q_bits - 1);
+    // only primes less than every candidate `q` can be used for the sieve
+    let sieve_primes: Vec<u32> = SIEVE_PRIMES
+        .iter()
+        .copied()
+        .take_while(|&s| top_bit > BigUint::from(s))
+        .collect();
+
+    loop {
+        let start = rng.gen_biguint(q_bits) | &top_bit | BigUint::from(1u8);
+        let mut residues: Vec<u32> = sieve_primes
+            .iter()
+            .map(|&s| (&start % s).to_u32().expect("less than s"))
+            .collect();
+
+        let mut q = start;
+        while q.bits() == q_bits {
+            // `q` is divisible by `s` if `r == 0`, and `2q + 1` is divisible by `s` if `r == (s - 1) / 2`
+            let sieved = sieve_primes
+                .iter()
+                .zip(residues.iter())
+                .all(|(&s, &r)| r != 0 && r != (s - 1) / 2);
+            if sieved {
+                let p: BigUint = (&q << 1) + 1u8;
+                // test both with a single round first to reject composites cheaply
+                if is_probable_prime_with_rng(&q, 1, rng)
+                    && is_probable_prime_with_rng(&p, 1, rng)
+                    && is_probable_prime_with_rng(&q, iter, rng)
+                    && is_probable_prime_with_rng(&p, iter, rng)
+                {
+                    return (q, p);
+                }
+            }
+            q += 2u8;
+            for (r, &s) in residues.iter_mut().zip(sieve_primes.iter()) {
+                *r = (*r + 2) % s;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use yoshi389111_miller_rabin::is_bpsw_prime;
+
+    #[test]
+    fn generate_safe_prime_returns_safe_prime() {
+        let mut rng = StdRng::seed_from_u64(389_111);
+        for bits in [3, 4, 5, 10, 64, 256] {
+            let p = generate_safe_prime_with_rng(bits, 20, &mut rng);
+            assert_eq!(p.bits(), bits);
+            assert!(is_bpsw_prime(&p));
+            assert!(is_bpsw_prime(&(&p >> 1)));
+        }
+    }
+
+    #[test]
+    fn generate_sophie_germain_prime_returns_sophie_germain_prime() {
+        let mut rng = StdRng::seed_from_u64(389_111);
+        for bits in [2, 3, 16, 128] {
+            let q = generate_sophie_germain_prime_with_rng(bits, 20, &mut rng);
+            assert_eq!(q.bits(), bits);
+            assert!(is_bpsw_prime(&q));
+            assert!(is_bpsw_prime(&((&q << 1) + 1u8)));
+        }
+    }
+}
@@ -5,6 +5,7 @@
 //! - Generates prime numbers on demand as an iterator
 //! - Supports arbitrary integer types (`usize`, `u32`, `i8`, etc.)
 //! - The iterator terminates after returning all prime numbers within the range that can be handled by the specified data type.
+//! - `no_std` with `alloc` when the default `std` feature is disabled
 //!
 //! ## Usage
 //!
@@ -29,7 +30,8 @@
 //!
 //! ## Performance
 //!
-//! - Memory usage depends on the prime range, as composite numbers are managed with a BTreeMap.
+//! - Memory usage depends on the prime range, as composite numbers are managed with a hash map
+//!   (a BTreeMap without the `std` feature).
 //! - Be cautious of memory consumption when handling large values.
 //!
 //! ## References
@@ -37,13 +39,23 @@
 //! - [Sieve of Eratosthenes - Wikipedia](https://en.wikipedia.org/wiki/Sieve_of_Eratosthenes)
 //!   - Incremental sieve
 
-use rustc_hash::FxHashMap;
-use std::hash::Hash;
-use std::iter::successors;
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use core::hash::Hash;
+use core::iter::successors;
+
+/// Map from the next composite number to the stride of its prime factor.
+#[cfg(feature = "std")]
+type SieveMap<T> = rustc_hash::FxHashMap<T, T>;
+/// Map from the next composite number to the stride of its prime factor.
+#[cfg(not(feature = "std"))]
+type SieveMap<T> = alloc::collections::BTreeMap<T, T>;
 
 /// Internal state for the prime number iterator using a sieve algorithm.
 struct PrimeSieveIter<T: num_traits::PrimInt + Hash> {
-    sieve_map: FxHashMap<T, T>,
+    sieve_map: SieveMap<T>,
     next_candidate: Option<T>,
 }
 
@@ -75,7 +87,7 @@ struct PrimeSieveIter<T: num_traits::PrimInt + Hash> {
 /// ```
 pub fn new<T: num_traits::PrimInt + Hash>() -> impl Iterator<Item = T> {
     PrimeSieveIter {
-        sieve_map: FxHashMap::default(),
+        sieve_map: SieveMap::default(),
         next_candidate: T::from(2u8),
     }
 }
@@ -118,6 +130,7 @@ impl<T: num_traits::PrimInt + Hash> Iterator for PrimeSieveIter<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::{vec, vec::Vec};
 
     #[test]
     fn test_prime_iterator_starting_values() {